@@ -0,0 +1,53 @@
+-- Payment requests and their lookup indexes
+-- IF NOT EXISTS throughout: databases created before migrations existed already have these
+
+CREATE TABLE IF NOT EXISTS payment_requests (
+    id UUID PRIMARY KEY,
+
+    -- Payment details
+    amount_lamports BIGINT NOT NULL,
+    token_symbol TEXT DEFAULT 'SOL',
+    memo TEXT UNIQUE NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, confirmed, underpaid, expired
+
+    -- Wallet addresses
+    receiver_address TEXT NOT NULL,         -- merchant wallet
+    sender_address TEXT,                    -- customer wallet (filled by the worker)
+
+    -- Blockchain info
+    tx_sig TEXT,
+    block_height BIGINT,
+
+    -- Timestamps
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    paid_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+
+    -- Merchant metadata
+    order_id TEXT,
+    customer_email TEXT,
+    metadata JSONB
+);
+
+-- Solana Pay reference key, added after the initial release
+ALTER TABLE payment_requests ADD COLUMN IF NOT EXISTS reference TEXT;
+
+-- Created while ORDER_ID_UNIQUE was on (see idx_order_id_unique)
+ALTER TABLE payment_requests ADD COLUMN IF NOT EXISTS order_id_unique BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_memo ON payment_requests(memo);
+CREATE INDEX IF NOT EXISTS idx_status ON payment_requests(status);
+CREATE INDEX IF NOT EXISTS idx_tx_sig ON payment_requests(tx_sig);
+CREATE INDEX IF NOT EXISTS idx_sender ON payment_requests(sender_address);
+-- Keyset pagination index; older databases have a single-column idx_created_at in its place
+DROP INDEX IF EXISTS idx_created_at;
+CREATE INDEX IF NOT EXISTS idx_payment_requests_created_id ON payment_requests(created_at, id);
+CREATE INDEX IF NOT EXISTS idx_reference ON payment_requests(reference);
+CREATE INDEX IF NOT EXISTS idx_order_id ON payment_requests(receiver_address, order_id);
+
+-- Only payments created with ORDER_ID_UNIQUE on, so turning the setting on
+-- never fails on older duplicates (the create handler checks those)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_order_id_unique
+ON payment_requests(receiver_address, order_id)
+WHERE order_id IS NOT NULL AND order_id_unique;
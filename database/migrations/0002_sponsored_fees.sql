@@ -0,0 +1,23 @@
+-- Network fees paid by the merchant's fee payer wallet
+-- One reservation per payment, holding the one signed transaction issued for it:
+-- released if the payment expires or is paid another way, settled with the
+-- real fee once the transaction lands (successfully or not)
+
+CREATE TABLE IF NOT EXISTS sponsored_fees (
+    id UUID PRIMARY KEY,
+    payment_id UUID NOT NULL UNIQUE REFERENCES payment_requests(id),
+    merchant_address TEXT NOT NULL,
+    fee_payer TEXT NOT NULL,
+    customer_account TEXT NOT NULL,
+    fee_lamports BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'reserved',
+    -- Fee payer signature, i.e. the id of the issued transaction
+    tx_sig TEXT NOT NULL,
+    -- Base64 wire format, signed by the fee payer only
+    signed_transaction TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    settled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_sponsored_fees_merchant ON sponsored_fees(merchant_address, created_at);
+CREATE INDEX IF NOT EXISTS idx_sponsored_fees_tx_sig ON sponsored_fees(tx_sig);
@@ -0,0 +1,17 @@
+-- Ordered log of payment state changes for pull-based consumers
+-- The feed reads in (tx_id, seq) order and only from transactions older than
+-- every one still running, so a late commit never lands behind a cursor
+
+CREATE TABLE IF NOT EXISTS events (
+    seq BIGSERIAL PRIMARY KEY,
+    tx_id XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    id UUID NOT NULL UNIQUE,
+    event_type TEXT NOT NULL,
+    merchant_address TEXT NOT NULL,
+    payment_id UUID,
+    data JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_merchant_tx_seq ON events(merchant_address, tx_id, seq);
+CREATE INDEX IF NOT EXISTS idx_events_created_at ON events(created_at);
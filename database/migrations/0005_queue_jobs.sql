@@ -0,0 +1,26 @@
+-- Jobs for the Postgres queue backend (QUEUE_BACKEND=postgres)
+-- status: ready | reserved | dead; run_at is the due time or lease deadline
+
+CREATE TABLE IF NOT EXISTS queue_jobs (
+    seq BIGSERIAL PRIMARY KEY,
+    queue TEXT NOT NULL,
+    job_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'ready',
+    attempts BIGINT NOT NULL DEFAULT 0,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_queue_jobs_due ON queue_jobs(queue, status, run_at);
+CREATE INDEX IF NOT EXISTS idx_queue_jobs_job_id ON queue_jobs(queue, job_id);
+
+-- Idempotency keys for the Postgres queue backend
+CREATE TABLE IF NOT EXISTS processed_jobs (
+    job_id TEXT PRIMARY KEY,
+    processed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_processed_jobs_at ON processed_jobs(processed_at);
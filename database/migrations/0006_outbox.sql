@@ -0,0 +1,25 @@
+-- Signatures the indexer has handled; written with the outbox row in one transaction
+
+CREATE TABLE IF NOT EXISTS processed_signatures (
+    signature TEXT PRIMARY KEY,
+    payment_id UUID,
+    processed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Jobs waiting to be relayed to the queue
+-- run_at is set for jobs due later (e.g. a payment's expiry job); the relay
+-- sends them right away as delayed jobs
+CREATE TABLE IF NOT EXISTS outbox (
+    id BIGSERIAL PRIMARY KEY,
+    queue TEXT NOT NULL,
+    job_id TEXT NOT NULL,
+    envelope JSONB NOT NULL,
+    run_at TIMESTAMPTZ,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbox_unsent ON outbox(id) WHERE sent_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_outbox_sent_at ON outbox(sent_at);
@@ -0,0 +1,21 @@
+-- One signature settles at most one payment
+-- Fails if existing rows already share a signature - resolve those first
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tx_sig_unique
+ON payment_requests(tx_sig)
+WHERE tx_sig IS NOT NULL;
+
+-- Signatures refused because the payment was already settled, expired,
+-- or the signature had already settled another payment
+CREATE TABLE IF NOT EXISTS confirmation_conflicts (
+    id UUID PRIMARY KEY,
+    payment_id UUID NOT NULL,
+    memo TEXT NOT NULL,
+    tx_sig TEXT NOT NULL,
+    sender_address TEXT NOT NULL,
+    amount_lamports BIGINT NOT NULL,
+    existing_status TEXT,
+    existing_tx_sig TEXT,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (payment_id, tx_sig)
+);
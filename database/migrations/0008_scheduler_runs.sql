@@ -0,0 +1,7 @@
+-- Last time a periodic job was queued; every worker replica runs a scheduler,
+-- and the one that moves this row forward is the one that queues the job
+
+CREATE TABLE IF NOT EXISTS scheduler_runs (
+    job_type TEXT PRIMARY KEY,
+    last_run_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::error::{ApiError, ErrorCode};
+use super::payments::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtClaims {
+    /// Expiry as a unix timestamp - tokens without one don't expire
+    exp: Option<i64>,
+}
+
+/// Require `Authorization: Bearer <JWT>` signed with JWT_SECRET (HS256)
+/// Guards the merchant-only routes: webhook endpoints, secrets and deliveries
+pub async fn require_merchant_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::new(ErrorCode::Unauthorized, "Missing bearer token"))?;
+
+    verify_jwt(&state.jwt_secret, token.trim(), Utc::now().timestamp())
+        .map_err(|e| ApiError::new(ErrorCode::Unauthorized, e))?;
+
+    Ok(next.run(request).await)
+}
+
+/// Check an HS256 JWT's signature and expiry
+pub fn verify_jwt(secret: &str, token: &str, now: i64) -> Result<(), String> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(claims), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("Malformed token".to_string());
+    };
+
+    let header: JwtHeader = decode_part(header)?;
+    if header.alg != "HS256" {
+        return Err(format!("Unsupported token algorithm {}", header.alg));
+    }
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| "Malformed token signature".to_string())?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(&token.as_bytes()[..token.rfind('.').unwrap_or(0)]);
+    // Constant-time comparison
+    mac.verify_slice(&signature)
+        .map_err(|_| "Invalid token signature".to_string())?;
+
+    let claims: JwtClaims = decode_part(claims)?;
+    if claims.exp.is_some_and(|exp| exp <= now) {
+        return Err("Token has expired".to_string());
+    }
+
+    Ok(())
+}
+
+fn decode_part<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T, String> {
+    let bytes = URL_SAFE_NO_PAD.decode(part).map_err(|_| "Malformed token".to_string())?;
+    serde_json::from_slice(&bytes).map_err(|_| "Malformed token".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SECRET: &str = "test-secret";
+    const NOW: i64 = 1_700_000_000;
+
+    fn token(secret: &str, header: serde_json::Value, claims: serde_json::Value) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(signing_input.as_bytes());
+        format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+    }
+
+    fn hs256() -> serde_json::Value {
+        json!({ "alg": "HS256", "typ": "JWT" })
+    }
+
+    #[test]
+    fn accepts_token_signed_with_the_secret() {
+        assert!(verify_jwt(SECRET, &token(SECRET, hs256(), json!({ "sub": "merchant" })), NOW).is_ok());
+        assert!(verify_jwt(SECRET, &token(SECRET, hs256(), json!({ "exp": NOW + 60 })), NOW).is_ok());
+    }
+
+    #[test]
+    fn rejects_other_secret_and_tampered_claims() {
+        assert!(verify_jwt(SECRET, &token("other", hs256(), json!({})), NOW).is_err());
+
+        let valid = token(SECRET, hs256(), json!({ "exp": NOW + 60 }));
+        let parts: Vec<&str> = valid.split('.').collect();
+        let tampered = format!("{}.{}.{}", parts[0], URL_SAFE_NO_PAD.encode(json!({}).to_string()), parts[2]);
+        assert!(verify_jwt(SECRET, &tampered, NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_expired_unsigned_and_malformed_tokens() {
+        assert_eq!(
+            verify_jwt(SECRET, &token(SECRET, hs256(), json!({ "exp": NOW })), NOW).unwrap_err(),
+            "Token has expired"
+        );
+        assert!(verify_jwt(SECRET, &token(SECRET, json!({ "alg": "none" }), json!({})), NOW).is_err());
+        assert!(verify_jwt(SECRET, "not-a-token", NOW).is_err());
+        assert!(verify_jwt(SECRET, "a.b.c.d", NOW).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Stable, machine-readable error codes returned by the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    PaymentNotFound,
+    WebhookNotFound,
+    DeliveryNotFound,
+    PaymentNotPending,
+    DuplicateOrderId,
+    InvalidPaymentId,
+    InvalidRequestBody,
+    InvalidAmount,
+    AmountTooSmall,
+    AmountTooLarge,
+    UnsupportedToken,
+    InvalidEmail,
+    FieldTooLong,
+    InvalidCharacters,
+    InvalidValue,
+    InvalidCursor,
+    InvalidAccount,
+    InvalidUrl,
+    UnsupportedEventType,
+    ValidationFailed,
+    Unauthorized,
+    UpstreamUnavailable,
+    DatabaseError,
+    InternalError,
+}
+
+impl ErrorCode {
+    /// HTTP status that goes with each error code
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::PaymentNotFound
+            | ErrorCode::WebhookNotFound
+            | ErrorCode::DeliveryNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::PaymentNotPending | ErrorCode::DuplicateOrderId => StatusCode::CONFLICT,
+            ErrorCode::InvalidPaymentId
+            | ErrorCode::InvalidRequestBody
+            | ErrorCode::InvalidAmount
+            | ErrorCode::AmountTooSmall
+            | ErrorCode::AmountTooLarge
+            | ErrorCode::UnsupportedToken
+            | ErrorCode::InvalidEmail
+            | ErrorCode::FieldTooLong
+            | ErrorCode::InvalidCharacters
+            | ErrorCode::InvalidValue
+            | ErrorCode::InvalidCursor
+            | ErrorCode::InvalidAccount
+            | ErrorCode::InvalidUrl
+            | ErrorCode::UnsupportedEventType
+            | ErrorCode::ValidationFailed => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::UpstreamUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::DatabaseError | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// API error - serialized as `{code, message, details}`
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    /// Create a new error with a code and message
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ApiError {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attach extra details (field errors, offending values, ...)
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn payment_not_found(id: &str) -> Self {
+        ApiError::new(ErrorCode::PaymentNotFound, format!("Payment {} not found", id))
+    }
+
+    pub fn invalid_payment_id(id: &str) -> Self {
+        ApiError::new(
+            ErrorCode::InvalidPaymentId,
+            format!("'{}' is not a valid payment id", id),
+        )
+    }
+
+    pub fn webhook_not_found(id: &str) -> Self {
+        ApiError::new(ErrorCode::WebhookNotFound, format!("Webhook endpoint {} not found", id))
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::new(ErrorCode::InternalError, message)
+    }
+}
+
+/// Map database errors - a missing row is a 404, anything else is a 500
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => {
+                ApiError::new(ErrorCode::PaymentNotFound, "Payment not found")
+            }
+            sqlx::Error::Database(ref db_err)
+                if db_err.constraint() == Some("idx_order_id_unique") =>
+            {
+                ApiError::new(
+                    ErrorCode::DuplicateOrderId,
+                    "A payment with this order_id already exists",
+                )
+            }
+            other => {
+                eprintln!("Database error: {}", other);
+                ApiError::new(ErrorCode::DatabaseError, "Database error")
+            }
+        }
+    }
+}
+
+/// Malformed or mistyped JSON bodies
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::new(ErrorCode::InvalidRequestBody, rejection.body_text())
+    }
+}
+
+/// Unparseable query strings
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(ErrorCode::InvalidValue, rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.code.status(), Json(self)).into_response()
+    }
+}
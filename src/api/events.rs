@@ -0,0 +1,143 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::payments::{find_payment, AppState};
+use crate::services::events::PaymentEvent;
+
+/// How long a stream waits on the event channel before re-reading the status
+/// from the database - covers events published while the Redis listener was
+/// reconnecting, or API instances running without Redis at all
+const RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// GET /payments/:id/events - Server-Sent Events stream of status changes
+/// Sends the current status first, then every change until a final status
+pub async fn payment_events_sse(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    // Subscribe before reading the row so no change slips in between
+    let receiver = state.events.subscribe();
+    let payment = find_payment(&state, &id).await?;
+    let initial = PaymentEvent::from(&payment);
+
+    let stream = stream::unfold(
+        (Some(initial), receiver, payment.status, false),
+        move |(initial, mut receiver, last_status, done)| {
+            let state = state.clone();
+            async move {
+                if done {
+                    return None;
+                }
+
+                let event = match initial {
+                    Some(event) => event,
+                    None => next_event(&state, &mut receiver, payment.id, &last_status).await?,
+                };
+
+                let done = event.is_final();
+                let sse = Event::default()
+                    .event("status")
+                    .json_data(&event)
+                    .unwrap_or_else(|_| Event::default().event("status"));
+
+                Some((Ok(sse), (None, receiver, event.status, done)))
+            }
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15))))
+}
+
+/// GET /payments/:id/ws - WebSocket alternative to the SSE stream
+pub async fn payment_events_ws(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let receiver = state.events.subscribe();
+    let payment = find_payment(&state, &id).await?;
+    let initial = PaymentEvent::from(&payment);
+
+    Ok(ws.on_upgrade(move |socket| stream_to_socket(state, socket, initial, receiver)))
+}
+
+async fn stream_to_socket(
+    state: AppState,
+    mut socket: WebSocket,
+    initial: PaymentEvent,
+    mut receiver: broadcast::Receiver<PaymentEvent>,
+) {
+    let payment_id = initial.payment_id;
+    let mut last_status = initial.status.clone();
+    let mut next = Some(initial);
+
+    loop {
+        let event = match next.take() {
+            Some(event) => event,
+            None => tokio::select! {
+                event = next_event(&state, &mut receiver, payment_id, &last_status) => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                // Client messages are ignored; a close or error ends the stream
+                msg = socket.recv() => match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+            },
+        };
+
+        last_status = event.status.clone();
+        let Ok(text) = serde_json::to_string(&event) else {
+            continue;
+        };
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+        if event.is_final() {
+            let _ = socket.send(Message::Close(None)).await;
+            break;
+        }
+    }
+}
+
+/// Wait for the next event belonging to one payment
+/// Events can be missed (a lagging receiver, a Redis reconnect), so the stored
+/// status is re-read after a lag and every `RESYNC_INTERVAL`, and returned as
+/// an event when it differs from `last_status`
+pub async fn next_event(
+    state: &AppState,
+    receiver: &mut broadcast::Receiver<PaymentEvent>,
+    payment_id: Uuid,
+    last_status: &str,
+) -> Option<PaymentEvent> {
+    loop {
+        match tokio::time::timeout(RESYNC_INTERVAL, receiver.recv()).await {
+            Ok(Ok(event)) if event.payment_id == payment_id => return Some(event),
+            Ok(Ok(_)) => continue,
+            Ok(Err(RecvError::Closed)) => return None,
+            // Skipped events may have included ours - check the database
+            Ok(Err(RecvError::Lagged(_))) | Err(_) => {}
+        }
+
+        match find_payment(state, &payment_id.to_string()).await {
+            Ok(payment) if payment.status != last_status => return Some(PaymentEvent::from(&payment)),
+            Ok(_) => {}
+            Err(e) => eprintln!("⚠️  Failed to re-read status of payment {}: {}", payment_id, e.message),
+        }
+    }
+}
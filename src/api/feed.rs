@@ -0,0 +1,108 @@
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::error::{ApiError, ErrorCode};
+use super::pagination::EventCursor;
+use super::payments::AppState;
+use crate::database::events::{events_after, StoredEvent};
+use crate::services::webhooks::EVENT_TYPES;
+
+/// Default and maximum page sizes for GET /events
+const DEFAULT_EVENT_PAGE_SIZE: i64 = 100;
+const MAX_EVENT_PAGE_SIZE: i64 = 500;
+
+/// GET /events query
+#[derive(Debug, Deserialize)]
+pub struct EventFeedQuery {
+    /// Cursor from a previous response; omitted starts at the oldest retained event
+    pub after: Option<String>,
+    /// Comma-separated event types, e.g. payment.confirmed,payment.expired
+    pub types: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// One event plus the cursor to resume right after it
+#[derive(Debug, Serialize)]
+pub struct FeedEvent {
+    #[serde(flatten)]
+    pub event: StoredEvent,
+    pub cursor: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventFeedResponse {
+    pub events: Vec<FeedEvent>,
+    /// Pass as `after` on the next call - unchanged when there is nothing new
+    pub next_cursor: String,
+    pub has_more: bool,
+}
+
+/// GET /events - Ordered feed of payment events for consumers that can't receive webhooks
+pub async fn get_events(
+    State(state): State<AppState>,
+    query: Result<Query<EventFeedQuery>, QueryRejection>,
+) -> Result<Json<EventFeedResponse>, ApiError> {
+    let Query(query) = query?;
+
+    let after = match query.after.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        Some(token) => EventCursor::decode(token).ok_or_else(|| {
+            ApiError::new(ErrorCode::InvalidCursor, "Cursor is invalid")
+        })?,
+        None => EventCursor { tx_id: 0, seq: 0 },
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_EVENT_PAGE_SIZE);
+    if !(1..=MAX_EVENT_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("limit must be between 1 and {}", MAX_EVENT_PAGE_SIZE),
+        ));
+    }
+
+    let mut types = Vec::new();
+    for event_type in query.types.as_deref().unwrap_or("").split(',') {
+        let event_type = event_type.trim();
+        if event_type.is_empty() {
+            continue;
+        }
+        if !EVENT_TYPES.contains(&event_type) {
+            return Err(ApiError::new(
+                ErrorCode::UnsupportedEventType,
+                format!(
+                    "Unsupported event type '{}', expected one of: {}",
+                    event_type,
+                    EVENT_TYPES.join(", ")
+                ),
+            ));
+        }
+        types.push(event_type.to_string());
+    }
+
+    // Fetch one extra row to know whether another page follows
+    let mut events = events_after(&state.db.pool, &state.wallet_address, after.tx_id, after.seq, &types, limit + 1).await?;
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+
+    let next_cursor = events
+        .last()
+        .map(|event| EventCursor { tx_id: event.tx_id, seq: event.seq })
+        .unwrap_or(after)
+        .encode();
+
+    let events = events
+        .into_iter()
+        .map(|event| FeedEvent {
+            cursor: EventCursor { tx_id: event.tx_id, seq: event.seq }.encode(),
+            event,
+        })
+        .collect();
+
+    Ok(Json(EventFeedResponse {
+        events,
+        next_cursor,
+        has_more,
+    }))
+}
@@ -0,0 +1,30 @@
+use axum::{extract::State, Json};
+use serde::Serialize;
+
+use super::error::ApiError;
+use super::payments::AppState;
+use crate::database::fees::{sponsored_fee_summary, SponsoredFeeSummary};
+
+/// Sponsored fee accounting for this merchant
+#[derive(Debug, Serialize)]
+pub struct SponsoredFeesResponse {
+    pub enabled: bool,
+    pub fee_payer: Option<String>,
+    pub daily_limit_lamports: Option<i64>,
+    #[serde(flatten)]
+    pub summary: SponsoredFeeSummary,
+}
+
+/// GET /sponsored-fees - Fees paid by the fee payer wallet for gasless checkouts
+pub async fn get_sponsored_fees(
+    State(state): State<AppState>,
+) -> Result<Json<SponsoredFeesResponse>, ApiError> {
+    let summary = sponsored_fee_summary(&state.db.pool, &state.wallet_address).await?;
+
+    Ok(Json(SponsoredFeesResponse {
+        enabled: state.fee_sponsor.is_some(),
+        fee_payer: state.fee_sponsor.as_ref().map(|sponsor| sponsor.pubkey().to_string()),
+        daily_limit_lamports: state.fee_sponsor.as_ref().map(|sponsor| sponsor.daily_limit_lamports),
+        summary,
+    }))
+}
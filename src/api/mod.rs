@@ -1,6 +1,17 @@
+pub mod auth;
+pub mod error;
+pub mod events;
+pub mod feed;
+pub mod fees;
+pub mod pagination;
 pub mod payments;
+pub mod qr;
+pub mod transaction_request;
+pub mod validation;
+pub mod webhooks;
 
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -10,11 +21,44 @@ use payments::AppState;
 
 /// Create the API router with all routes
 pub fn create_router(state: AppState) -> Router {
+    // Merchant webhook endpoints - bearer token required
+    let webhook_routes = Router::new()
+        .route("/webhooks", get(webhooks::list_webhooks).post(webhooks::create_webhook))
+        .route(
+            "/webhooks/:id",
+            get(webhooks::get_webhook)
+                .patch(webhooks::update_webhook)
+                .delete(webhooks::delete_webhook),
+        )
+        .route("/webhooks/:id/rotate-secret", post(webhooks::rotate_webhook_secret))
+        .route("/webhooks/:id/test", post(webhooks::test_webhook))
+        .route("/webhooks/:id/deliveries", get(webhooks::list_webhook_deliveries))
+        .route("/webhook-deliveries/:id/replay", post(webhooks::replay_webhook_delivery))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_merchant_token));
+
     Router::new()
         // Payment routes
         .route("/payments/create", post(payments::create_payment))
+        .route("/payments/status", post(payments::batch_payment_status))
         .route("/payments/:id", get(payments::get_payment_status))
+        .route("/payments/:id/events", get(events::payment_events_sse))
+        .route("/payments/:id/ws", get(events::payment_events_ws))
+        .route("/payments/:id/qr.svg", get(qr::get_payment_qr_svg))
+        .route("/payments/:id/qr.png", get(qr::get_payment_qr_png))
+        .route(
+            "/payments/:id/transaction-request",
+            get(transaction_request::get_transaction_request)
+                .post(transaction_request::post_transaction_request),
+        )
+        .route("/payments/by-order/:order_id", get(payments::get_payment_by_order))
+        .route("/payments/by-memo/:memo", get(payments::get_payment_by_memo))
+        .route("/payments/by-tx/:signature", get(payments::get_payment_by_tx))
         .route("/payments", get(payments::list_payments))
+        // Pull-based event feed
+        .route("/events", get(feed::get_events))
+        // Fee sponsorship accounting
+        .route("/sponsored-fees", get(fees::get_sponsored_fees))
+        .merge(webhook_routes)
         // Add CORS support
         .layer(CorsLayer::permissive())
         // Share state with all routes
@@ -0,0 +1,123 @@
+use uuid::Uuid;
+
+pub use crate::database::payments::{SortField, SortOrder};
+
+/// Keyset cursor - position of the last row on the previous page
+/// Encoded as base58 so clients treat it as an opaque token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub sort: SortField,
+    pub order: SortOrder,
+    /// Sort key of the last row (created_at in microseconds or amount)
+    pub value: i64,
+    pub id: Uuid,
+}
+
+impl PageCursor {
+    pub fn encode(&self) -> String {
+        let raw = format!(
+            "{}|{}|{}|{}",
+            self.sort.as_str(),
+            self.order.as_str(),
+            self.value,
+            self.id
+        );
+        bs58::encode(raw).into_string()
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = bs58::decode(token).into_vec().ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let mut parts = raw.split('|');
+
+        let cursor = PageCursor {
+            sort: SortField::parse(parts.next()?)?,
+            order: SortOrder::parse(parts.next()?)?,
+            value: parts.next()?.parse().ok()?,
+            id: Uuid::parse_str(parts.next()?).ok()?,
+        };
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(cursor)
+    }
+}
+
+/// Position in the /events feed - transaction id and sequence number of the last event seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventCursor {
+    pub tx_id: i64,
+    pub seq: i64,
+}
+
+impl EventCursor {
+    pub fn encode(&self) -> String {
+        bs58::encode(format!("evt|{}|{}", self.tx_id, self.seq)).into_string()
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = bs58::decode(token).into_vec().ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let (tx_id, seq) = raw.strip_prefix("evt|")?.split_once('|')?;
+
+        Some(EventCursor {
+            tx_id: tx_id.parse().ok()?,
+            seq: seq.parse().ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_cursor_round_trips() {
+        let cursor = PageCursor {
+            sort: SortField::Amount,
+            order: SortOrder::Asc,
+            value: -42,
+            id: Uuid::new_v4(),
+        };
+
+        assert_eq!(PageCursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn page_cursor_is_opaque() {
+        let cursor = PageCursor {
+            sort: SortField::CreatedAt,
+            order: SortOrder::Desc,
+            value: 1_700_000_000_000_000,
+            id: Uuid::nil(),
+        };
+
+        assert!(!cursor.encode().contains('|'));
+    }
+
+    #[test]
+    fn page_cursor_rejects_garbage() {
+        let encode = |raw: &str| bs58::encode(raw).into_string();
+        let id = Uuid::new_v4();
+
+        assert_eq!(PageCursor::decode("not base58 0OIl"), None);
+        assert_eq!(PageCursor::decode(&encode("created_at|desc|1")), None);
+        assert_eq!(PageCursor::decode(&encode(&format!("status|desc|1|{}", id))), None);
+        assert_eq!(PageCursor::decode(&encode(&format!("amount|sideways|1|{}", id))), None);
+        assert_eq!(PageCursor::decode(&encode(&format!("amount|asc|x|{}", id))), None);
+        assert_eq!(PageCursor::decode(&encode(&format!("amount|asc|1|{}|extra", id))), None);
+        // Event cursors are not page cursors
+        assert_eq!(PageCursor::decode(&EventCursor { tx_id: 3, seq: 7 }.encode()), None);
+    }
+
+    #[test]
+    fn event_cursor_round_trips() {
+        let cursor = EventCursor { tx_id: 987, seq: 12_345 };
+
+        assert_eq!(EventCursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(EventCursor::decode(&bs58::encode("12345").into_string()), None);
+        assert_eq!(EventCursor::decode(&bs58::encode("evt|12345").into_string()), None);
+    }
+}
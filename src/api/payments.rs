@@ -1,67 +1,133 @@
 use axum::{
-    extract::{State, Path},
-    http::StatusCode,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Path, Query, State,
+    },
+    response::{IntoResponse, Response},
     Json,
 };
 use uuid::Uuid;
-use chrono::{Utc, Duration};
-
-
-
+use chrono::{DateTime, Utc, Duration};
 
+use super::error::{ApiError, ErrorCode};
+use super::pagination::{PageCursor, SortField, SortOrder};
+use super::validation::{validate_create_payment, validation_error, FieldError};
+use super::events::next_event;
 use crate::database::models::{
-    CreatePaymentRequest, PaymentRequest, PaymentResponse, PaymentStatusResponse, generate_memo
+    BatchStatusRequest, BatchStatusResponse, PaymentStatusQuery,
+    CreatePaymentRequest, ListPaymentsQuery, PaymentListResponse, PaymentRequest, PaymentResponse,
+    PaymentStatusResponse, generate_memo
 };
+use crate::config::tokens::find_token;
+use crate::database::payments::{NewPayment, PageRequest, PaymentRepository};
 use crate::database::Database;
+use crate::services::events::{is_final_status, PaymentEvent};
+use crate::services::fee_sponsor::FeeSponsor;
+use crate::services::transaction_builder::BlockhashSource;
+use crate::services::webhooks::WebhookDispatcher;
+use crate::utils::solana_pay::{format_amount, generate_reference, TransferRequest};
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Default and maximum page sizes for GET /payments
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Rows returned by the legacy (unpaginated) GET /payments array
+const LEGACY_PAGE_SIZE: i64 = 100;
+
+/// Longest long-poll on GET /payments/:id?wait=N
+const MAX_WAIT_SECS: u64 = 60;
+
+/// Most payment ids accepted by POST /payments/status
+const MAX_BATCH_IDS: usize = 500;
 
 /// App state with database
 #[derive(Clone)]
 pub struct AppState {
     pub db: Database,
+    /// Payment persistence (Postgres in production)
+    pub payments: Arc<dyn PaymentRepository>,
     pub wallet_address: String,
+    /// One payment per order_id (ORDER_ID_UNIQUE)
+    pub order_id_unique: bool,
+    pub merchant_label: String,
+    /// PNG logo overlaid on rendered QR codes
+    pub merchant_logo: Option<Arc<Vec<u8>>>,
+    /// Icon URL returned to wallets by the Solana Pay transaction request endpoint
+    pub merchant_icon_url: Option<String>,
+    /// Recent blockhash provider for server-built transactions
+    pub blockhash_source: Arc<dyn BlockhashSource>,
+    /// Hot wallet paying network fees for gasless checkout (optional)
+    pub fee_sponsor: Option<Arc<FeeSponsor>>,
+    /// Payment status changes fanned out to SSE/WebSocket clients
+    pub events: broadcast::Sender<PaymentEvent>,
+    /// Signed outbound webhooks to merchant endpoints
+    pub webhooks: WebhookDispatcher,
+    /// HS256 key for the bearer tokens guarding the webhook API
+    pub jwt_secret: String,
 }
 
 /// POST /payments/create - Create payment request with unique memo
 pub async fn create_payment(
     State(state): State<AppState>,
-    Json(payload): Json<CreatePaymentRequest>,
-) -> Result<Json<PaymentResponse>, StatusCode> {
-    
+    payload: Result<Json<CreatePaymentRequest>, JsonRejection>,
+) -> Result<Json<PaymentResponse>, ApiError> {
+    let Json(payload) = payload?;
+    let payload = validate_create_payment(payload)?;
+
     let payment_id = Uuid::new_v4();
     let memo = generate_memo();
     let now = Utc::now();
     let expires_at = now + Duration::minutes(15); // 15 minute expiry
-    let token_symbol = payload.token_symbol.unwrap_or_else(|| "SOL".to_string());
-
-    // Insert payment request into database
-    sqlx::query(
-        r#"
-        INSERT INTO payment_requests 
-        (id, amount_lamports, token_symbol, memo, status, receiver_address, created_at, expires_at, order_id, customer_email)
-        VALUES ($1, $2, $3, $4, 'pending', $5, $6, $7, $8, $9)
-        "#,
-    )
-    .bind(payment_id)
-    .bind(payload.amount_lamports)
-    .bind(&token_symbol)
-    .bind(&memo)
-    .bind(&state.wallet_address)
-    .bind(now)
-    .bind(expires_at)
-    .bind(payload.order_id)
-    .bind(payload.customer_email)
-    .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let token_symbol = payload.token.symbol.to_string();
+    let reference = generate_reference();
+
+    // The unique index only covers payments created with the setting on - check older ones here
+    if let (true, Some(order_id)) = (state.order_id_unique, &payload.order_id)
+        && state.payments.find_by_order(&state.wallet_address, order_id).await?.is_some()
+    {
+        return Err(ApiError::new(
+            ErrorCode::DuplicateOrderId,
+            "A payment with this order_id already exists",
+        ));
+    }
+
+    // Insert payment request, its payment.created event and webhook deliveries together
+    state
+        .payments
+        .create(NewPayment {
+            id: payment_id,
+            amount_lamports: payload.amount_lamports,
+            token_symbol: token_symbol.clone(),
+            memo: memo.clone(),
+            receiver_address: state.wallet_address.clone(),
+            created_at: now,
+            expires_at,
+            order_id: payload.order_id.clone(),
+            order_id_unique: state.order_id_unique,
+            customer_email: payload.customer_email,
+            reference: reference.clone(),
+        })
+        .await?;
+
+    let solana_pay_url = TransferRequest {
+        recipient: state.wallet_address.clone(),
+        amount: Some(payload.amount_lamports as u64),
+        decimals: payload.token.decimals,
+        spl_token: payload.token.mint.map(str::to_string),
+        references: vec![reference.clone()],
+        label: Some(state.merchant_label.clone()),
+        message: payload.order_id.as_ref().map(|order_id| format!("Order {}", order_id)),
+        memo: Some(memo.clone()),
+    }
+    .to_url();
 
-    let amount_sol = payload.amount_lamports as f64 / 1_000_000_000.0;
     let instructions = format!(
-        "Send {} SOL to {} with memo: {}",
-        amount_sol,
+        "Send {} {} to {} with memo: {}",
+        format_amount(payload.amount_lamports as u64, payload.token.decimals),
+        token_symbol,
         state.wallet_address,
         memo
     );
@@ -74,6 +140,8 @@ pub async fn create_payment(
         token_symbol,
         receiver_address: state.wallet_address.clone(),
         memo,
+        reference,
+        solana_pay_url,
         instructions,
         status: "pending".to_string(),
         created_at: now,
@@ -82,58 +150,320 @@ pub async fn create_payment(
 }
 
 /// GET /payments/:id - Get payment status
+/// With `?wait=N` the request is held until the status changes or N seconds pass
 pub async fn get_payment_status(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<PaymentStatusResponse>, StatusCode> {
-    
-    let payment_id = Uuid::parse_str(&id)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
-
-    let payment = sqlx::query_as::<_, PaymentRequest>(
-        r#"
-        SELECT * FROM payment_requests
-        WHERE id = $1
-        "#,
-    )
-    .bind(payment_id)
-    .fetch_one(&state.db.pool)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error: {}", e);
-        StatusCode::NOT_FOUND
-    })?;
+    query: Result<Query<PaymentStatusQuery>, QueryRejection>,
+) -> Result<Json<PaymentStatusResponse>, ApiError> {
+    let Query(query) = query?;
+    let wait = query.wait.unwrap_or(0);
+
+    if wait > MAX_WAIT_SECS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("wait must be at most {} seconds", MAX_WAIT_SECS),
+        ));
+    }
+
+    // Subscribe before reading so a change between the read and the wait isn't missed
+    let mut receiver = state.events.subscribe();
+    let mut payment = find_payment(&state, &id).await?;
+
+    if wait > 0 && !is_final_status(&payment.status) {
+        let changed = tokio::time::timeout(
+            std::time::Duration::from_secs(wait),
+            next_event(&state, &mut receiver, payment.id, &payment.status),
+        )
+        .await;
+
+        if let Ok(Some(_)) = changed {
+            payment = find_payment(&state, &id).await?;
+        }
+    }
 
     println!("📊 Payment status checked: {} - Status: {}", id, payment.status);
 
-    Ok(Json(PaymentStatusResponse::from(payment)))
+    Ok(Json(status_response(&state, payment)))
 }
 
-/// GET /payments - List all payments
-pub async fn list_payments(
+/// POST /payments/status - Statuses for many payments in one round-trip
+pub async fn batch_payment_status(
     State(state): State<AppState>,
-) -> Result<Json<Vec<PaymentStatusResponse>>, StatusCode> {
-    
-    let payments = sqlx::query_as::<_, PaymentRequest>(
-        r#"
-        SELECT * FROM payment_requests
-        ORDER BY created_at DESC
-        LIMIT 100
-        "#,
-    )
-    .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+    payload: Result<Json<BatchStatusRequest>, JsonRejection>,
+) -> Result<Json<BatchStatusResponse>, ApiError> {
+    let Json(payload) = payload?;
+
+    if payload.ids.is_empty() || payload.ids.len() > MAX_BATCH_IDS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("ids must contain between 1 and {} payment ids", MAX_BATCH_IDS),
+        ));
+    }
+
+    let mut ids = Vec::with_capacity(payload.ids.len());
+    let mut errors = Vec::new();
+    for id in &payload.ids {
+        match Uuid::parse_str(id) {
+            Ok(uuid) => ids.push(uuid),
+            Err(_) => errors.push(FieldError {
+                field: "ids",
+                code: ErrorCode::InvalidPaymentId,
+                message: format!("'{}' is not a valid payment id", id),
+            }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(validation_error(errors));
+    }
+
+    let rows = state.payments.get_many(&ids).await?;
+
+    // Answer in request order
+    let found: HashMap<Uuid, PaymentRequest> = rows.into_iter().map(|p| (p.id, p)).collect();
+    let mut payments = Vec::with_capacity(found.len());
+    let mut not_found = Vec::new();
+    for (id, uuid) in payload.ids.iter().zip(&ids) {
+        match found.get(uuid) {
+            Some(payment) => payments.push(status_response(&state, payment.clone())),
+            None => not_found.push(id.clone()),
+        }
+    }
+
+    println!("📊 Batch status checked: {} payments", payments.len());
+
+    Ok(Json(BatchStatusResponse {
+        payments,
+        not_found,
+    }))
+}
+
+/// GET /payments/by-order/:order_id - Latest payment for one of our order IDs
+pub async fn get_payment_by_order(
+    State(state): State<AppState>,
+    Path(order_id): Path<String>,
+) -> Result<Json<PaymentStatusResponse>, ApiError> {
+    let payment = state
+        .payments
+        .find_by_order(&state.wallet_address, &order_id)
+        .await?
+        .ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::PaymentNotFound,
+            format!("No payment found for order {}", order_id),
+        )
     })?;
 
+    Ok(Json(status_response(&state, payment)))
+}
+
+/// GET /payments/by-memo/:memo - Lookup by the memo the customer attached
+pub async fn get_payment_by_memo(
+    State(state): State<AppState>,
+    Path(memo): Path<String>,
+) -> Result<Json<PaymentStatusResponse>, ApiError> {
+    let payment = state
+        .payments
+        .find_by_memo(&state.wallet_address, &memo)
+        .await?
+        .ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::PaymentNotFound,
+            format!("No payment found for memo {}", memo),
+        )
+    })?;
+
+    Ok(Json(status_response(&state, payment)))
+}
+
+/// GET /payments/by-tx/:signature - Lookup by confirming transaction signature
+pub async fn get_payment_by_tx(
+    State(state): State<AppState>,
+    Path(signature): Path<String>,
+) -> Result<Json<PaymentStatusResponse>, ApiError> {
+    let payment = state
+        .payments
+        .find_by_tx(&state.wallet_address, &signature)
+        .await?
+        .ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::PaymentNotFound,
+            format!("No payment found for transaction {}", signature),
+        )
+    })?;
+
+    Ok(Json(status_response(&state, payment)))
+}
+
+/// GET /payments - List payments with filters, sorting and cursor pagination
+pub async fn list_payments(
+    State(state): State<AppState>,
+    query: Result<Query<ListPaymentsQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+
+    let mut errors = Vec::new();
+
+    let sort = match query.sort.as_deref() {
+        None => SortField::CreatedAt,
+        Some(value) => SortField::parse(value).unwrap_or_else(|| {
+            errors.push(FieldError {
+                field: "sort",
+                code: ErrorCode::InvalidValue,
+                message: format!("Unknown sort field '{}', expected created_at or amount", value),
+            });
+            SortField::CreatedAt
+        }),
+    };
+
+    let order = match query.order.as_deref() {
+        None => SortOrder::Desc,
+        Some(value) => SortOrder::parse(value).unwrap_or_else(|| {
+            errors.push(FieldError {
+                field: "order",
+                code: ErrorCode::InvalidValue,
+                message: format!("Unknown sort order '{}', expected asc or desc", value),
+            });
+            SortOrder::Desc
+        }),
+    };
+
+    // Without `paginate` clients keep the old bare array of the latest 100 payments
+    let default_limit = if query.paginate { DEFAULT_PAGE_SIZE } else { LEGACY_PAGE_SIZE };
+    let limit = query.limit.unwrap_or(default_limit);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        errors.push(FieldError {
+            field: "limit",
+            code: ErrorCode::InvalidValue,
+            message: format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+        });
+    }
+
+    if let (Some(min), Some(max)) = (query.min_amount, query.max_amount)
+        && min > max
+    {
+        errors.push(FieldError {
+            field: "min_amount",
+            code: ErrorCode::InvalidValue,
+            message: "min_amount must not be greater than max_amount".to_string(),
+        });
+    }
+
+    // Cursors are only valid for the sort they were issued with, and only
+    // paginated responses return the next one
+    let cursor = match query.cursor.as_deref() {
+        None => None,
+        Some(_) if !query.paginate => {
+            errors.push(FieldError {
+                field: "cursor",
+                code: ErrorCode::InvalidValue,
+                message: "cursor requires paginate=true".to_string(),
+            });
+            None
+        }
+        Some(token) => match PageCursor::decode(token) {
+            Some(cursor) if cursor.sort == sort && cursor.order == order => Some(cursor),
+            _ => {
+                errors.push(FieldError {
+                    field: "cursor",
+                    code: ErrorCode::InvalidCursor,
+                    message: "Cursor is invalid or was issued for a different sort".to_string(),
+                });
+                None
+            }
+        },
+    };
+
+    if !errors.is_empty() {
+        return Err(validation_error(errors));
+    }
+
+    // Cursors for created_at hold microseconds - reject ones out of timestamp range
+    let out_of_range = |cursor: &PageCursor| DateTime::<Utc>::from_timestamp_micros(cursor.value).is_none();
+    if sort == SortField::CreatedAt && cursor.as_ref().is_some_and(out_of_range) {
+        return Err(ApiError::new(ErrorCode::InvalidCursor, "Cursor is invalid"));
+    }
+
+    // Page query: fetch one extra row to know whether another page exists
+    let filter = query.filter();
+    let page = PageRequest {
+        sort,
+        order,
+        after: cursor.map(|cursor| (cursor.value, cursor.id)),
+        limit: limit + 1,
+    };
+    let mut payments = state.payments.list(&filter, &page).await?;
+
+    let next_cursor = if payments.len() as i64 > limit {
+        payments.truncate(limit as usize);
+        payments
+            .last()
+            .map(|last| PageCursor { sort, order, value: sort.key(last), id: last.id }.encode())
+    } else {
+        None
+    };
+
+    // Total count is optional - it costs a second query
+    let total_count = if query.paginate && query.include_total {
+        Some(state.payments.count(&filter).await?)
+    } else {
+        None
+    };
+
     println!("📊 Fetched {} payment requests", payments.len());
 
-    let response: Vec<PaymentStatusResponse> = payments
+    let payments: Vec<PaymentStatusResponse> = payments
         .into_iter()
-        .map(PaymentStatusResponse::from)
+        .map(|payment| status_response(&state, payment))
         .collect();
 
-    Ok(Json(response))
+    if !query.paginate {
+        return Ok(Json(payments).into_response());
+    }
+
+    Ok(Json(PaymentListResponse {
+        payments,
+        next_cursor,
+        total_count,
+    })
+    .into_response())
+}
+
+/// Load a payment by its gateway UUID (as given in the path)
+pub async fn find_payment(state: &AppState, id: &str) -> Result<PaymentRequest, ApiError> {
+    let payment_id = Uuid::parse_str(id)
+        .map_err(|_| ApiError::invalid_payment_id(id))?;
+
+    let payment = state
+        .payments
+        .get(payment_id)
+        .await?
+        .ok_or_else(|| ApiError::payment_not_found(id))?;
+
+    Ok(payment)
+}
+
+/// Status response with the same Solana Pay URL clients got on create
+fn status_response(state: &AppState, payment: PaymentRequest) -> PaymentStatusResponse {
+    let url = solana_pay_url(state, &payment);
+    PaymentStatusResponse::from(payment).with_solana_pay_url(url)
+}
+
+/// Rebuild the Solana Pay transfer request URL for a stored payment
+pub fn solana_pay_url(state: &AppState, payment: &PaymentRequest) -> Option<String> {
+    let token = find_token(&payment.token_symbol)?;
+
+    let request = TransferRequest {
+        recipient: payment.receiver_address.clone(),
+        amount: u64::try_from(payment.amount_lamports).ok(),
+        decimals: token.decimals,
+        spl_token: token.mint.map(str::to_string),
+        references: payment.reference.iter().cloned().collect(),
+        label: Some(state.merchant_label.clone()),
+        message: payment.order_id.as_ref().map(|order_id| format!("Order {}", order_id)),
+        memo: Some(payment.memo.clone()),
+    };
+
+    Some(request.to_url())
 }
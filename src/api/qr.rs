@@ -0,0 +1,169 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::error::{ApiError, ErrorCode};
+use super::payments::{find_payment, solana_pay_url, AppState};
+use crate::database::models::PaymentRequest;
+use crate::utils::qr::{self, QrOptions};
+
+/// Longest a cache may keep a code without revalidating - the encoded URL
+/// follows the merchant config, and the code stops being payable at expiry
+const MAX_AGE_SECS: i64 = 60;
+
+/// Query parameters for the QR endpoints
+#[derive(Debug, Deserialize)]
+pub struct QrQuery {
+    /// Image width/height in pixels (64-2048, default 300)
+    pub size: Option<u32>,
+    /// Quiet zone in modules (0-16, default 4)
+    pub margin: Option<u32>,
+    /// Overlay the merchant logo (default: on when a logo is configured)
+    pub logo: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QrFormat {
+    Svg,
+    Png,
+}
+
+impl QrFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            QrFormat::Svg => "svg",
+            QrFormat::Png => "png",
+        }
+    }
+}
+
+/// GET /payments/:id/qr.svg - Solana Pay QR code as SVG
+pub async fn get_payment_qr_svg(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    query: Result<Query<QrQuery>, QueryRejection>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+    render_qr(&state, &id, &query, &headers, QrFormat::Svg).await
+}
+
+/// GET /payments/:id/qr.png - Solana Pay QR code as PNG
+pub async fn get_payment_qr_png(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    query: Result<Query<QrQuery>, QueryRejection>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+    render_qr(&state, &id, &query, &headers, QrFormat::Png).await
+}
+
+async fn render_qr(
+    state: &AppState,
+    id: &str,
+    query: &QrQuery,
+    headers: &HeaderMap,
+    format: QrFormat,
+) -> Result<Response, ApiError> {
+    let payment = find_payment(state, id).await?;
+    let url = solana_pay_url(state, &payment).ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::UnsupportedToken,
+            format!("Cannot build a Solana Pay URL for token {}", payment.token_symbol),
+        )
+    })?;
+
+    let size = query.size.unwrap_or(qr::DEFAULT_SIZE);
+    if !(qr::MIN_SIZE..=qr::MAX_SIZE).contains(&size) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("size must be between {} and {}", qr::MIN_SIZE, qr::MAX_SIZE),
+        ));
+    }
+
+    let margin = query.margin.unwrap_or(qr::DEFAULT_MARGIN);
+    if margin > qr::MAX_MARGIN {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("margin must be at most {}", qr::MAX_MARGIN),
+        ));
+    }
+
+    let logo = match (query.logo, state.merchant_logo.as_ref()) {
+        (Some(false), _) | (_, None) => None,
+        (_, Some(logo)) => Some(logo.as_slice()),
+    };
+
+    // ETag covers everything that affects the rendered bytes, plus the status
+    // so a settled or expired payment isn't revalidated as unchanged
+    // Variable-length fields are length-prefixed so they can't run together
+    let mut hasher = Sha256::new();
+    hasher.update((url.len() as u64).to_be_bytes());
+    hasher.update(url.as_bytes());
+    hasher.update(size.to_be_bytes());
+    hasher.update(margin.to_be_bytes());
+    hasher.update(format.as_str());
+    hasher.update((payment.status.len() as u64).to_be_bytes());
+    hasher.update(payment.status.as_bytes());
+    if let Some(logo) = logo {
+        hasher.update(logo);
+    }
+    let etag = format!("\"{}\"", hex::encode(&hasher.finalize()[..16]));
+    let cache_control = cache_control(&payment);
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+
+    if not_modified {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, cache_control)],
+        )
+            .into_response());
+    }
+
+    let options = QrOptions { size, margin, logo };
+
+    let (content_type, body) = match format {
+        QrFormat::Svg => (
+            "image/svg+xml",
+            qr::render_svg(&url, &options).map_err(ApiError::internal)?.into_bytes(),
+        ),
+        QrFormat::Png => (
+            "image/png",
+            qr::render_png(&url, &options).map_err(ApiError::internal)?,
+        ),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Cache briefly, never past the payment's expiry; revalidate every time once it isn't pending
+fn cache_control(payment: &PaymentRequest) -> String {
+    if payment.status != "pending" {
+        return "no-cache".to_string();
+    }
+
+    let remaining = payment
+        .expires_at
+        .map(|expires_at| (expires_at - Utc::now()).num_seconds())
+        .unwrap_or(MAX_AGE_SECS);
+
+    format!("public, max-age={}", remaining.clamp(0, MAX_AGE_SECS))
+}
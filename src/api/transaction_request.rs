@@ -0,0 +1,207 @@
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+use std::str::FromStr;
+
+use super::error::{ApiError, ErrorCode};
+use super::payments::{find_payment, AppState};
+use crate::config::tokens::find_token;
+use crate::database::fees::{find_sponsored_fee, reserve_sponsored_fee, NewSponsoredFee};
+use crate::database::models::PaymentRequest;
+use crate::services::fee_sponsor::{estimate_fee, FeeSponsor};
+use crate::services::transaction_builder::{
+    build_payment_transaction, encode_transaction, PaymentTransactionParams,
+};
+
+/// How long a signed transaction is handed out again - roughly the blockhash
+/// lifetime (150 slots), after which it can no longer land
+const SIGNED_TRANSACTION_TTL_SECS: i64 = 60;
+
+/// GET response - shown by the wallet before it asks for the account
+#[derive(Debug, Serialize)]
+pub struct TransactionRequestMetadata {
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// POST body sent by the wallet
+#[derive(Debug, Deserialize)]
+pub struct TransactionRequestBody {
+    pub account: String,
+}
+
+/// POST response - unsigned transaction for the wallet to sign and send
+#[derive(Debug, Serialize)]
+pub struct TransactionRequestResponse {
+    pub transaction: String,
+    pub message: String,
+}
+
+/// GET /payments/:id/transaction-request - Solana Pay label and icon
+pub async fn get_transaction_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<TransactionRequestMetadata>, ApiError> {
+    // Only answer for payments that exist
+    find_payment(&state, &id).await?;
+
+    Ok(Json(TransactionRequestMetadata {
+        label: state.merchant_label.clone(),
+        icon: state.merchant_icon_url.clone(),
+    }))
+}
+
+/// POST /payments/:id/transaction-request - Build the payment transaction server-side
+pub async fn post_transaction_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    payload: Result<Json<TransactionRequestBody>, JsonRejection>,
+) -> Result<Json<TransactionRequestResponse>, ApiError> {
+    let Json(payload) = payload?;
+
+    let payer = Pubkey::from_str(payload.account.trim()).map_err(|_| {
+        ApiError::new(
+            ErrorCode::InvalidAccount,
+            format!("'{}' is not a valid Solana account", payload.account),
+        )
+    })?;
+
+    let payment = find_payment(&state, &id).await?;
+
+    if payment.status != "pending" {
+        return Err(ApiError::new(
+            ErrorCode::PaymentNotPending,
+            format!("Payment is {}", payment.status),
+        ));
+    }
+    if payment.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(ApiError::new(ErrorCode::PaymentNotPending, "Payment has expired"));
+    }
+
+    let token = find_token(&payment.token_symbol).ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::UnsupportedToken,
+            format!("Unsupported token {}", payment.token_symbol),
+        )
+    })?;
+
+    let recipient = Pubkey::from_str(&payment.receiver_address)
+        .map_err(|_| ApiError::internal("Merchant address is not a valid public key"))?;
+    let reference = payment
+        .reference
+        .as_deref()
+        .and_then(|reference| Pubkey::from_str(reference).ok());
+    let amount = u64::try_from(payment.amount_lamports)
+        .map_err(|_| ApiError::internal("Stored amount is negative"))?;
+
+    // RPC client is blocking - keep it off the async runtime
+    let source = state.blockhash_source.clone();
+    let blockhash = tokio::task::spawn_blocking(move || source.latest_blockhash())
+        .await
+        .map_err(|e| ApiError::internal(format!("Blockhash task failed: {}", e)))?
+        .map_err(|e| ApiError::new(ErrorCode::UpstreamUnavailable, e))?;
+
+    let params = PaymentTransactionParams {
+        payer,
+        fee_payer: None,
+        amount,
+        recipient,
+        token,
+        reference,
+        memo: &payment.memo,
+    };
+
+    // Gasless checkout: the merchant's hot wallet pays the fee while under its daily limit
+    let sponsor = state.fee_sponsor.as_ref().filter(|sponsor| sponsor.covers(token));
+    let sponsored_transaction = match sponsor {
+        Some(sponsor) => sponsored_transaction(&state, sponsor, &payment, &params, blockhash).await?,
+        None => None,
+    };
+    let sponsored = sponsored_transaction.is_some();
+
+    let transaction = match sponsored_transaction {
+        Some(transaction) => transaction,
+        None => {
+            let transaction = build_payment_transaction(&params, blockhash).map_err(ApiError::internal)?;
+            encode_transaction(&transaction).map_err(ApiError::internal)?
+        }
+    };
+
+    println!(
+        "🧾 Transaction request built for payment {} (payer {}, fee sponsored: {})",
+        payment.id, payer, sponsored
+    );
+
+    let message = if sponsored {
+        format!("{} - {} (network fee covered by merchant)", state.merchant_label, payment.memo)
+    } else {
+        format!("{} - {}", state.merchant_label, payment.memo)
+    };
+
+    Ok(Json(TransactionRequestResponse {
+        transaction,
+        message,
+    }))
+}
+
+/// The payment's sponsored transaction, or None when the customer pays the fee
+/// A payment gets one signed transaction, so only one fee can ever be charged to
+/// the fee payer for it: asking again returns the same transaction while it can
+/// still land, and after that (or for another account) the customer pays
+async fn sponsored_transaction(
+    state: &AppState,
+    sponsor: &FeeSponsor,
+    payment: &PaymentRequest,
+    params: &PaymentTransactionParams<'_>,
+    blockhash: Hash,
+) -> Result<Option<String>, ApiError> {
+    let customer_account = params.payer.to_string();
+
+    if let Some(issued) = find_sponsored_fee(&state.db.pool, payment.id).await? {
+        let fresh = issued.created_at > Utc::now() - Duration::seconds(SIGNED_TRANSACTION_TTL_SECS);
+        if issued.status == "reserved" && fresh && issued.customer_account == customer_account {
+            return Ok(Some(issued.signed_transaction));
+        }
+        println!("⚠️  Sponsored transaction already issued for payment {}, customer pays the fee", payment.id);
+        return Ok(None);
+    }
+
+    let params = PaymentTransactionParams {
+        fee_payer: Some(sponsor.pubkey()),
+        ..params.clone()
+    };
+    let mut transaction = build_payment_transaction(&params, blockhash).map_err(ApiError::internal)?;
+    let fee_lamports = estimate_fee(&transaction);
+    sponsor.partial_sign(&mut transaction).map_err(ApiError::internal)?;
+
+    // Fee payer signs first, so its signature is the transaction id
+    let tx_sig = transaction.signatures[0].to_string();
+    let signed_transaction = encode_transaction(&transaction).map_err(ApiError::internal)?;
+
+    let reserved = reserve_sponsored_fee(
+        &state.db.pool,
+        &NewSponsoredFee {
+            payment_id: payment.id,
+            merchant_address: &payment.receiver_address,
+            fee_payer: &sponsor.pubkey().to_string(),
+            customer_account: &customer_account,
+            fee_lamports,
+            tx_sig: &tx_sig,
+            signed_transaction: &signed_transaction,
+        },
+        sponsor.daily_limit_lamports,
+    )
+    .await?;
+
+    if !reserved {
+        println!("⚠️  Fee not sponsored for payment {} (limit reached for {}), customer pays the fee", payment.id, payment.receiver_address);
+        return Ok(None);
+    }
+
+    Ok(Some(signed_transaction))
+}
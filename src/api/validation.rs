@@ -0,0 +1,204 @@
+use serde::Serialize;
+
+use super::error::{ApiError, ErrorCode};
+use crate::config::tokens::{find_token, TokenInfo, SUPPORTED_TOKENS};
+use crate::database::models::CreatePaymentRequest;
+use crate::services::webhooks::EVENT_TYPES;
+
+pub const MAX_ORDER_ID_LEN: usize = 64;
+pub const MAX_EMAIL_LEN: usize = 254;
+pub const MAX_WEBHOOK_URL_LEN: usize = 2048;
+
+/// A single invalid field
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// Create payment request after validation
+#[derive(Debug)]
+pub struct ValidatedPayment {
+    pub amount_lamports: i64,
+    pub token: &'static TokenInfo,
+    pub order_id: Option<String>,
+    pub customer_email: Option<String>,
+}
+
+/// Validate a create payment request
+/// Every field is checked so the client gets all problems in one response
+pub fn validate_create_payment(payload: CreatePaymentRequest) -> Result<ValidatedPayment, ApiError> {
+    let mut errors = Vec::new();
+
+    // Token symbol (defaults to SOL)
+    let symbol = payload.token_symbol.as_deref().unwrap_or("SOL").trim();
+    let token = find_token(symbol);
+    if token.is_none() {
+        let supported: Vec<&str> = SUPPORTED_TOKENS.iter().map(|t| t.symbol).collect();
+        errors.push(FieldError {
+            field: "token_symbol",
+            code: ErrorCode::UnsupportedToken,
+            message: format!("Unsupported token '{}', expected one of: {}", symbol, supported.join(", ")),
+        });
+    }
+
+    // Amount - positive and within the token's limits
+    let amount = payload.amount_lamports;
+    if amount <= 0 {
+        errors.push(FieldError {
+            field: "amount_lamports",
+            code: ErrorCode::InvalidAmount,
+            message: "amount_lamports must be greater than zero".to_string(),
+        });
+    } else if let Some(token) = token {
+        if amount < token.min_amount {
+            errors.push(FieldError {
+                field: "amount_lamports",
+                code: ErrorCode::AmountTooSmall,
+                message: format!("Minimum amount for {} is {}", token.symbol, token.min_amount),
+            });
+        } else if amount > token.max_amount {
+            errors.push(FieldError {
+                field: "amount_lamports",
+                code: ErrorCode::AmountTooLarge,
+                message: format!("Maximum amount for {} is {}", token.symbol, token.max_amount),
+            });
+        }
+    }
+
+    // Order ID - optional, bounded, printable
+    let order_id = normalize_optional(payload.order_id);
+    if let Some(ref order_id) = order_id {
+        if order_id.chars().count() > MAX_ORDER_ID_LEN {
+            errors.push(FieldError {
+                field: "order_id",
+                code: ErrorCode::FieldTooLong,
+                message: format!("order_id must be at most {} characters", MAX_ORDER_ID_LEN),
+            });
+        } else if order_id.chars().any(char::is_control) {
+            errors.push(FieldError {
+                field: "order_id",
+                code: ErrorCode::InvalidCharacters,
+                message: "order_id must not contain control characters".to_string(),
+            });
+        }
+    }
+
+    // Customer email - optional, bounded, syntactically valid
+    let customer_email = normalize_optional(payload.customer_email);
+    if let Some(ref email) = customer_email {
+        if email.len() > MAX_EMAIL_LEN {
+            errors.push(FieldError {
+                field: "customer_email",
+                code: ErrorCode::FieldTooLong,
+                message: format!("customer_email must be at most {} characters", MAX_EMAIL_LEN),
+            });
+        } else if !is_valid_email(email) {
+            errors.push(FieldError {
+                field: "customer_email",
+                code: ErrorCode::InvalidEmail,
+                message: format!("'{}' is not a valid email address", email),
+            });
+        }
+    }
+
+    match token {
+        Some(token) if errors.is_empty() => Ok(ValidatedPayment {
+            amount_lamports: amount,
+            token,
+            order_id,
+            customer_email,
+        }),
+        _ => Err(validation_error(errors)),
+    }
+}
+
+/// Validate a webhook endpoint URL and event type filter
+/// Returns the trimmed URL and de-duplicated event types
+pub fn validate_webhook_endpoint(
+    url: &str,
+    event_types: &[String],
+) -> Result<(String, Vec<String>), ApiError> {
+    let mut errors = Vec::new();
+
+    let url = url.trim().to_string();
+    if url.len() > MAX_WEBHOOK_URL_LEN {
+        errors.push(FieldError {
+            field: "url",
+            code: ErrorCode::FieldTooLong,
+            message: format!("url must be at most {} characters", MAX_WEBHOOK_URL_LEN),
+        });
+    } else {
+        match reqwest::Url::parse(&url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host().is_some() => {}
+            _ => errors.push(FieldError {
+                field: "url",
+                code: ErrorCode::InvalidUrl,
+                message: format!("'{}' is not a valid http(s) URL", url),
+            }),
+        }
+    }
+
+    let mut types: Vec<String> = Vec::new();
+    for event_type in event_types {
+        let event_type = event_type.trim();
+        if !EVENT_TYPES.contains(&event_type) {
+            errors.push(FieldError {
+                field: "event_types",
+                code: ErrorCode::UnsupportedEventType,
+                message: format!(
+                    "Unsupported event type '{}', expected one of: {}",
+                    event_type,
+                    EVENT_TYPES.join(", ")
+                ),
+            });
+        } else if !types.iter().any(|t| t == event_type) {
+            types.push(event_type.to_string());
+        }
+    }
+
+    if errors.is_empty() {
+        Ok((url, types))
+    } else {
+        Err(validation_error(errors))
+    }
+}
+
+/// Build the single error response carrying all field errors
+pub fn validation_error(errors: Vec<FieldError>) -> ApiError {
+    ApiError::new(ErrorCode::ValidationFailed, "Request validation failed")
+        .with_details(serde_json::json!({ "errors": errors }))
+}
+
+/// Trim strings and treat empty ones as missing
+fn normalize_optional(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Basic email syntax check: local@domain.tld, no whitespace
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+
+    if local.is_empty() || local.len() > 64 || domain.contains('@') {
+        return false;
+    }
+
+    if email.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+
+    // Domain needs at least one dot and no empty labels
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
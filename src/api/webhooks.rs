@@ -0,0 +1,404 @@
+use axum::{
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::error::{ApiError, ErrorCode};
+use super::payments::AppState;
+use super::validation::{validate_webhook_endpoint, validation_error, FieldError};
+use crate::services::webhooks::{
+    generate_secret, DeliveryAttempt, WebhookDelivery, WebhookEndpoint, WebhookEvent,
+    EVENT_PAYMENT_CONFIRMED, EVENT_TYPES,
+};
+
+/// Default and longest overlap window for secret rotation
+const DEFAULT_ROTATION_OVERLAP_SECS: i64 = 24 * 60 * 60;
+const MAX_ROTATION_OVERLAP_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Default and maximum page sizes for GET /webhooks/:id/deliveries
+const DEFAULT_DELIVERY_PAGE_SIZE: i64 = 50;
+const MAX_DELIVERY_PAGE_SIZE: i64 = 200;
+
+/// POST /webhooks body
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Empty or missing subscribes to every event type
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+/// Endpoint plus its signing secret - only returned when the secret is issued
+#[derive(Debug, Serialize)]
+pub struct WebhookSecretResponse {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+/// PATCH /webhooks/:id body - only the given fields change
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+/// POST /webhooks/:id/rotate-secret body
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateSecretRequest {
+    /// How long the old secret keeps signing deliveries (default 24h, 0 revokes it now)
+    pub overlap_secs: Option<i64>,
+}
+
+/// POST /webhooks/:id/test body
+#[derive(Debug, Default, Deserialize)]
+pub struct TestWebhookRequest {
+    pub event_type: Option<String>,
+}
+
+/// GET /webhooks/:id/deliveries query
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    pub limit: Option<i64>,
+}
+
+/// Outcome of a test or replayed delivery
+#[derive(Debug, Serialize)]
+pub struct DeliveryResultResponse {
+    pub endpoint_id: Uuid,
+    pub event: WebhookEvent,
+    pub attempt: i32,
+    pub success: bool,
+    #[serde(flatten)]
+    pub result: DeliveryAttempt,
+}
+
+/// POST /webhooks - Register a webhook endpoint for this merchant
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    payload: Result<Json<CreateWebhookRequest>, JsonRejection>,
+) -> Result<Json<WebhookSecretResponse>, ApiError> {
+    let Json(payload) = payload?;
+    let (url, event_types) = validate_webhook_endpoint(&payload.url, &payload.event_types)?;
+    check_public_url(&state, &url).await?;
+
+    let secret = generate_secret();
+    let now = Utc::now();
+
+    let endpoint = sqlx::query_as::<_, WebhookEndpoint>(
+        r#"
+        INSERT INTO webhook_endpoints
+        (id, merchant_address, url, secret, event_types, active, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, TRUE, $6, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(&state.wallet_address)
+    .bind(&url)
+    .bind(&secret)
+    .bind(&event_types)
+    .bind(now)
+    .fetch_one(&state.db.pool)
+    .await?;
+
+    println!("🪝 Webhook endpoint registered: {} -> {}", endpoint.id, endpoint.url);
+
+    Ok(Json(WebhookSecretResponse { endpoint, secret }))
+}
+
+/// GET /webhooks - Webhook endpoints registered for this merchant
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WebhookEndpoint>>, ApiError> {
+    let endpoints = sqlx::query_as::<_, WebhookEndpoint>(
+        r#"
+        SELECT * FROM webhook_endpoints
+        WHERE merchant_address = $1
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(&state.wallet_address)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(endpoints))
+}
+
+/// GET /webhooks/:id - One webhook endpoint
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookEndpoint>, ApiError> {
+    Ok(Json(find_webhook(&state, &id).await?))
+}
+
+/// PATCH /webhooks/:id - Change URL, event filter or enable/disable
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    payload: Result<Json<UpdateWebhookRequest>, JsonRejection>,
+) -> Result<Json<WebhookEndpoint>, ApiError> {
+    let Json(payload) = payload?;
+    let endpoint = find_webhook(&state, &id).await?;
+
+    let url = payload.url.unwrap_or(endpoint.url);
+    let event_types = payload.event_types.unwrap_or(endpoint.event_types);
+    let (url, event_types) = validate_webhook_endpoint(&url, &event_types)?;
+    check_public_url(&state, &url).await?;
+    let active = payload.active.unwrap_or(endpoint.active);
+
+    let endpoint = sqlx::query_as::<_, WebhookEndpoint>(
+        r#"
+        UPDATE webhook_endpoints
+        SET url = $3, event_types = $4, active = $5, updated_at = NOW()
+        WHERE id = $1 AND merchant_address = $2
+        RETURNING *
+        "#,
+    )
+    .bind(endpoint.id)
+    .bind(&state.wallet_address)
+    .bind(&url)
+    .bind(&event_types)
+    .bind(active)
+    .fetch_optional(&state.db.pool)
+    .await?
+    .ok_or_else(|| ApiError::webhook_not_found(&id))?;
+
+    println!("🪝 Webhook endpoint updated: {} (active: {})", endpoint.id, endpoint.active);
+
+    Ok(Json(endpoint))
+}
+
+/// DELETE /webhooks/:id - Remove an endpoint and its delivery log
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let endpoint_id = parse_id(&id, "webhook")?;
+
+    let result = sqlx::query("DELETE FROM webhook_endpoints WHERE id = $1 AND merchant_address = $2")
+        .bind(endpoint_id)
+        .bind(&state.wallet_address)
+        .execute(&state.db.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::webhook_not_found(&id));
+    }
+
+    println!("🪝 Webhook endpoint deleted: {}", endpoint_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /webhooks/:id/rotate-secret - Issue a new secret
+/// The old one keeps signing deliveries (as a second v1) until the overlap ends
+pub async fn rotate_webhook_secret(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    payload: Option<Json<RotateSecretRequest>>,
+) -> Result<Json<WebhookSecretResponse>, ApiError> {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let overlap_secs = payload.overlap_secs.unwrap_or(DEFAULT_ROTATION_OVERLAP_SECS);
+
+    if !(0..=MAX_ROTATION_OVERLAP_SECS).contains(&overlap_secs) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("overlap_secs must be between 0 and {}", MAX_ROTATION_OVERLAP_SECS),
+        ));
+    }
+
+    let endpoint = find_webhook(&state, &id).await?;
+    let secret = generate_secret();
+    let previous_expires_at = Utc::now() + Duration::seconds(overlap_secs);
+
+    let endpoint = sqlx::query_as::<_, WebhookEndpoint>(
+        r#"
+        UPDATE webhook_endpoints
+        SET previous_secret = secret,
+            previous_secret_expires_at = $3,
+            secret = $4,
+            updated_at = NOW()
+        WHERE id = $1 AND merchant_address = $2
+        RETURNING *
+        "#,
+    )
+    .bind(endpoint.id)
+    .bind(&state.wallet_address)
+    .bind(previous_expires_at)
+    .bind(&secret)
+    .fetch_optional(&state.db.pool)
+    .await?
+    .ok_or_else(|| ApiError::webhook_not_found(&id))?;
+
+    println!("🔑 Webhook secret rotated: {} (old secret valid for {}s)", endpoint.id, overlap_secs);
+
+    Ok(Json(WebhookSecretResponse { endpoint, secret }))
+}
+
+/// POST /webhooks/:id/test - Send one synthetic event and report the result
+/// Sent even if the endpoint is disabled or filters out the event type
+pub async fn test_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    payload: Option<Json<TestWebhookRequest>>,
+) -> Result<Json<DeliveryResultResponse>, ApiError> {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let event_type = payload.event_type.as_deref().unwrap_or(EVENT_PAYMENT_CONFIRMED).trim();
+
+    if !EVENT_TYPES.contains(&event_type) {
+        return Err(ApiError::new(
+            ErrorCode::UnsupportedEventType,
+            format!(
+                "Unsupported event type '{}', expected one of: {}",
+                event_type,
+                EVENT_TYPES.join(", ")
+            ),
+        ));
+    }
+
+    let endpoint = find_webhook(&state, &id).await?;
+
+    let event = WebhookEvent::new(
+        event_type,
+        json!({
+            "test": true,
+            "payment_id": Uuid::nil(),
+            "status": "test",
+            "message": "Test event sent from the payment gateway",
+        }),
+    );
+
+    println!("🧪 Sending test {} webhook to {}", event_type, endpoint.url);
+
+    let result = state.webhooks.deliver(&endpoint, &event, 1).await;
+
+    Ok(Json(delivery_result(endpoint.id, event, 1, result)))
+}
+
+/// GET /webhooks/:id/deliveries - Most recent delivery attempts for an endpoint
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    query: Result<Query<DeliveriesQuery>, QueryRejection>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let Query(query) = query?;
+    let limit = query.limit.unwrap_or(DEFAULT_DELIVERY_PAGE_SIZE);
+
+    if !(1..=MAX_DELIVERY_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidValue,
+            format!("limit must be between 1 and {}", MAX_DELIVERY_PAGE_SIZE),
+        ));
+    }
+
+    let endpoint = find_webhook(&state, &id).await?;
+
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        SELECT * FROM webhook_deliveries
+        WHERE endpoint_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(endpoint.id)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    Ok(Json(deliveries))
+}
+
+/// POST /webhook-deliveries/:id/replay - Redeliver a logged event to its endpoint
+/// The event keeps its original id so receivers can de-duplicate
+pub async fn replay_webhook_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DeliveryResultResponse>, ApiError> {
+    let delivery_id = parse_id(&id, "delivery")?;
+
+    let delivery = sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        SELECT d.* FROM webhook_deliveries d
+        JOIN webhook_endpoints e ON e.id = d.endpoint_id
+        WHERE d.id = $1 AND e.merchant_address = $2
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(&state.wallet_address)
+    .fetch_optional(&state.db.pool)
+    .await?
+    .ok_or_else(|| {
+        ApiError::new(ErrorCode::DeliveryNotFound, format!("Webhook delivery {} not found", id))
+    })?;
+
+    let endpoint = find_webhook(&state, &delivery.endpoint_id.to_string()).await?;
+    let attempt = state.webhooks.next_attempt(endpoint.id, delivery.event_id).await?;
+    let event = delivery.payload;
+
+    println!("🔁 Replaying webhook {} ({}) to {}", event.id, event.event_type, endpoint.url);
+
+    let result = state.webhooks.deliver(&endpoint, &event, attempt).await;
+
+    Ok(Json(delivery_result(endpoint.id, event, attempt, result)))
+}
+
+/// Refuse endpoints on loopback, private or link-local addresses
+async fn check_public_url(state: &AppState, url: &str) -> Result<(), ApiError> {
+    state.webhooks.check_url(url).await.map_err(|e| {
+        validation_error(vec![FieldError {
+            field: "url",
+            code: ErrorCode::InvalidUrl,
+            message: e,
+        }])
+    })
+}
+
+/// Load a webhook endpoint owned by this merchant
+async fn find_webhook(state: &AppState, id: &str) -> Result<WebhookEndpoint, ApiError> {
+    let endpoint_id = parse_id(id, "webhook")?;
+
+    sqlx::query_as::<_, WebhookEndpoint>(
+        r#"
+        SELECT * FROM webhook_endpoints
+        WHERE id = $1 AND merchant_address = $2
+        "#,
+    )
+    .bind(endpoint_id)
+    .bind(&state.wallet_address)
+    .fetch_optional(&state.db.pool)
+    .await?
+    .ok_or_else(|| ApiError::webhook_not_found(id))
+}
+
+fn parse_id(id: &str, kind: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|_| {
+        ApiError::new(ErrorCode::InvalidValue, format!("'{}' is not a valid {} id", id, kind))
+    })
+}
+
+fn delivery_result(
+    endpoint_id: Uuid,
+    event: WebhookEvent,
+    attempt: i32,
+    result: DeliveryAttempt,
+) -> DeliveryResultResponse {
+    DeliveryResultResponse {
+        endpoint_id,
+        event,
+        attempt,
+        success: result.succeeded(),
+        result,
+    }
+}
@@ -1,5 +1,12 @@
-use payment_gateway_rust::{Config, Database, QueueService};
-use payment_gateway_rust::indexer::{SolanaIndexer, parse_transaction, payment_to_confirmation_job};
+use payment_gateway_rust::{Config, Database};
+use payment_gateway_rust::database::fees::settle_failed_sponsored_fee;
+use payment_gateway_rust::database::outbox::{is_signature_processed, record_matched_payment};
+use payment_gateway_rust::database::payments::{PaymentRepository, PgPaymentRepository};
+use payment_gateway_rust::services::jobs::JobEnvelope;
+use payment_gateway_rust::services::outbox::OutboxRelay;
+use payment_gateway_rust::services::queue::{self, CONFIRMATION_QUEUE};
+use payment_gateway_rust::indexer::{SolanaIndexer, failed_transaction_fee, parse_transaction, payment_to_confirmation_job};
+use payment_gateway_rust::utils::shutdown::shutdown_signal;
 use std::collections::HashSet;
 use std::time::Duration;
 use tokio::time;
@@ -26,11 +33,11 @@ async fn main() {
         }
     };
 
-    // Connect to Redis queue
-    let mut queue = match QueueService::new(&config.redis_url).await {
+    // Connect to the job queue the outbox relays to
+    let queue = match queue::connect(&config).await {
         Ok(q) => q,
         Err(e) => {
-            eprintln!("❌ Failed to connect to Redis: {}", e);
+            eprintln!("❌ Failed to connect to queue: {}", e);
             std::process::exit(1);
         }
     };
@@ -59,6 +66,11 @@ async fn main() {
         }
     }
 
+    // Relay confirmation jobs from the outbox to the queue
+    OutboxRelay::new(db.pool.clone(), queue).spawn();
+
+    let payments = PgPaymentRepository::new(db.pool.clone());
+
     println!("👀 Monitoring blockchain for payments...");
     println!("📋 Watching wallet: {}", wallet_address);
     println!("💡 Waiting for transactions with memos...\n");
@@ -66,11 +78,19 @@ async fn main() {
     // Track processed signatures to avoid duplicates
     let mut processed_signatures: HashSet<String> = HashSet::new();
 
-    // Main indexing loop
+    // Main indexing loop - stops between passes on SIGINT/SIGTERM
     let mut interval = time::interval(Duration::from_secs(5));
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = &mut shutdown => {
+                println!("\n🛑 Shutdown requested, stopping indexer");
+                break;
+            }
+        }
 
         // Get recent transaction signatures
         let signatures = match indexer.get_recent_signatures(10) {
@@ -88,6 +108,19 @@ async fn main() {
                 continue;
             }
 
+            // Handled before a restart - no need to fetch it again
+            match is_signature_processed(&db.pool, &signature).await {
+                Ok(true) => {
+                    processed_signatures.insert(signature.clone());
+                    continue;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("❌ Failed to check signature {}: {}", signature, e);
+                    continue;
+                }
+            }
+
             println!("🔍 Found new transaction: {}", signature);
 
             // Get full transaction details
@@ -103,6 +136,18 @@ async fn main() {
                 }
             };
 
+            // A failed sponsored transaction still cost our fee payer its fee
+            if let Some(fee) = failed_transaction_fee(&tx) {
+                match settle_failed_sponsored_fee(&db.pool, &signature, fee).await {
+                    Ok(true) => println!("💸 Sponsored transaction {} failed, {} lamports fee settled", signature, fee),
+                    Ok(false) => {}
+                    Err(e) => {
+                        eprintln!("❌ Failed to settle fee of failed transaction {}: {}\n", signature, e);
+                        continue;
+                    }
+                }
+            }
+
             // Parse transaction to extract payment data
             if let Some(payment) = parse_transaction(&tx, &signature, &wallet_address) {
                 // Check if memo exists
@@ -114,58 +159,46 @@ async fn main() {
 
                 let memo = payment.memo.as_ref().unwrap();
 
-                // Check if payment request exists with this memo
-                let payment_exists: Result<(uuid::Uuid,), sqlx::Error> = sqlx::query_as(
-                    "SELECT id FROM payment_requests WHERE memo = $1 AND status = 'pending'"
-                )
-                .bind(memo)
-                .fetch_one(&db.pool)
-                .await;
-
-                match payment_exists {
-                    Ok((payment_id,)) => {
-                        println!("✅ Found matching payment request: {}", payment_id);
+                // Check if payment request exists with this memo - whatever its status,
+                // so a second payment for a settled memo is logged as a conflict
+                match payments.find_by_memo(&wallet_address, memo).await {
+                    Ok(Some(matched)) => {
+                        let payment_id = matched.id;
+                        if matched.status == "pending" {
+                            println!("✅ Found matching payment request: {}", payment_id);
+                        } else {
+                            println!("⚠️  Payment request {} is already {}, queueing so the duplicate is logged", payment_id, matched.status);
+                        }
                         
                         // Convert to confirmation job
-                        if let Some(job) = payment_to_confirmation_job(payment) {
-                            let job_json = serde_json::json!({
-                                "payment_id": payment_id.to_string(),
-                                "memo": job.memo,
-                                "sender_address": job.sender_address,
-                                "tx_sig": job.tx_sig,
-                                "amount_lamports": job.amount_lamports,
-                                "paid_at": job.paid_at,
-                            });
-
-                            // Check if already processed (idempotency)
-                            match queue.is_job_processed(&job.job_id).await {
-                                Ok(true) => {
-                                    println!("⚠️  Payment {} already processed, skipping\n", job.job_id);
+                        if let Some(job) = payment_to_confirmation_job(payment, payment_id) {
+                            let envelope = match JobEnvelope::new(&job) {
+                                Ok(envelope) => envelope,
+                                Err(e) => {
+                                    eprintln!("❌ Invalid confirmation job for {}: {}\n", signature, e);
                                     processed_signatures.insert(signature.clone());
                                     continue;
                                 }
+                            };
+
+                            // Signature and job are written in one transaction;
+                            // the outbox relay puts the job on the queue
+                            match record_matched_payment(&db.pool, &signature, payment_id, CONFIRMATION_QUEUE, &envelope).await {
+                                Ok(true) => {
+                                    println!("✅ Confirmation job {} added to outbox for payment: {}\n", envelope.id, payment_id);
+                                    processed_signatures.insert(signature.clone());
+                                }
                                 Ok(false) => {
-                                    // Push to confirmation queue
-                                    match queue.push_confirmation_job(job_json).await {
-                                        Ok(_) => {
-                                            println!("✅ Confirmation job queued for payment: {}\n", payment_id);
-                                            processed_signatures.insert(signature.clone());
-                                            
-                                            // Mark as processed
-                                            let _ = queue.mark_job_processed(&job.job_id).await;
-                                        }
-                                        Err(e) => {
-                                            eprintln!("❌ Failed to queue confirmation: {}\n", e);
-                                        }
-                                    }
+                                    println!("⚠️  Payment {} already processed, skipping\n", job.job_id);
+                                    processed_signatures.insert(signature.clone());
                                 }
                                 Err(e) => {
-                                    eprintln!("❌ Failed to check job status: {}\n", e);
+                                    eprintln!("❌ Failed to record confirmation: {}\n", e);
                                 }
                             }
                         }
                     }
-                    Err(sqlx::Error::RowNotFound) => {
+                    Ok(None) => {
                         println!("⚠️  No matching payment request found for memo: {}", memo);
                         println!("   (Payment may be for different merchant or memo is invalid)\n");
                         processed_signatures.insert(signature.clone());
@@ -186,4 +219,7 @@ async fn main() {
             println!("🧹 Cleared processed signatures cache\n");
         }
     }
+
+    // Anything matched but not yet relayed stays in the outbox for the next run
+    println!("👋 Indexer stopped");
 }
@@ -0,0 +1,65 @@
+use payment_gateway_rust::{Config, Database};
+
+const USAGE: &str = "\
+Usage: migrate [command]
+
+Commands:
+  run      Apply pending migrations (default)
+  status   List migrations and when each was applied
+
+Migrations live in database/migrations and are embedded in every binary.";
+
+#[tokio::main]
+async fn main() {
+    let command = std::env::args().nth(1);
+
+    let config = match Config::from_env() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("❌ Failed to load config: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let db = match Database::new(&config.database_url).await {
+        Ok(database) => database,
+        Err(e) => {
+            eprintln!("❌ Failed to connect to database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match command.as_deref() {
+        None | Some("run") => {
+            if let Err(e) = db.migrate().await {
+                eprintln!("❌ Migration failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some("status") => status(&db).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+}
+
+async fn status(db: &Database) {
+    let migrations = match db.migration_status().await {
+        Ok(migrations) => migrations,
+        Err(e) => {
+            eprintln!("❌ Failed to read migration status: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let pending = migrations.iter().filter(|migration| migration.applied_at.is_none()).count();
+    println!("📜 {} migration(s), {} pending", migrations.len(), pending);
+
+    for migration in migrations {
+        match migration.applied_at {
+            Some(at) => println!("   ✅ {:04} {} (applied {})", migration.version, migration.description, at),
+            None => println!("   ⏳ {:04} {} (pending)", migration.version, migration.description),
+        }
+    }
+}
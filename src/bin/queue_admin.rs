@@ -0,0 +1,164 @@
+use payment_gateway_rust::services::queue::{self, QueueError, CONFIRMATION_QUEUE};
+use payment_gateway_rust::{Config, JobQueue};
+
+const USAGE: &str = "\
+Usage: queue-admin [--queue <name>] <command>
+
+Commands:
+  stats                Queue depth, in-flight jobs, consumer lag and dead letters
+  list [limit]         Show dead letters, newest first (default 20)
+  requeue <id>         Put one dead letter back on its queue
+  requeue --all        Put every dead letter back
+  purge <id>           Delete one dead letter
+  purge --all          Delete every dead letter
+  scheduled [limit]    Show jobs waiting for their run time, soonest first (default 20)
+  cancel <id>          Drop a scheduled job before it runs
+
+The queue defaults to confirmation_queue.";
+
+#[tokio::main]
+async fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut queue_name = CONFIRMATION_QUEUE.to_string();
+    if let Some(pos) = args.iter().position(|arg| arg == "--queue") {
+        if pos + 1 >= args.len() {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+        queue_name = args.remove(pos + 1);
+        args.remove(pos);
+    }
+
+    let config = match Config::from_env() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("❌ Failed to load config: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut queue = match queue::connect(&config).await {
+        Ok(q) => q,
+        Err(e) => {
+            eprintln!("❌ Failed to connect to queue: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let command = args.first().map(String::as_str);
+    let target = args.get(1).map(String::as_str);
+
+    let result = match (command, target) {
+        (Some("stats"), None) => stats(queue.as_mut(), &queue_name).await,
+        (Some("list"), limit) => {
+            let limit = match limit.map(str::parse::<isize>) {
+                None => 20,
+                Some(Ok(limit)) if limit > 0 => limit,
+                Some(_) => {
+                    eprintln!("❌ limit must be a positive number");
+                    std::process::exit(2);
+                }
+            };
+            list(queue.as_mut(), &queue_name, limit).await
+        }
+        (Some("scheduled"), limit) => {
+            let limit = match limit.map(str::parse::<isize>) {
+                None => 20,
+                Some(Ok(limit)) if limit > 0 => limit,
+                Some(_) => {
+                    eprintln!("❌ limit must be a positive number");
+                    std::process::exit(2);
+                }
+            };
+            scheduled(queue.as_mut(), &queue_name, limit).await
+        }
+        (Some("cancel"), Some(id)) => queue.cancel_scheduled(&queue_name, id).await.map(|found| {
+            if found {
+                println!("🚫 Cancelled {}", id);
+            } else {
+                println!("⚠️  No scheduled job {} on {} (it may already have run)", id, queue_name);
+            }
+        }),
+        (Some("requeue"), Some("--all")) => queue
+            .requeue_all_dead_letters(&queue_name)
+            .await
+            .map(|count| println!("📤 Requeued {} dead letter(s) on {}", count, queue_name)),
+        (Some("requeue"), Some(id)) => queue.requeue_dead_letter(&queue_name, id).await.map(|found| {
+            if found {
+                println!("📤 Requeued {}", id);
+            } else {
+                println!("⚠️  No dead letter {} on {}", id, queue_name);
+            }
+        }),
+        (Some("purge"), Some("--all")) => queue
+            .purge_dead_letters(&queue_name)
+            .await
+            .map(|count| println!("🗑️  Purged {} dead letter(s) from {}", count, queue_name)),
+        (Some("purge"), Some(id)) => queue.purge_dead_letter(&queue_name, id).await.map(|found| {
+            if found {
+                println!("🗑️  Purged {}", id);
+            } else {
+                println!("⚠️  No dead letter {} on {}", id, queue_name);
+            }
+        }),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("❌ Queue error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn stats(queue: &mut dyn JobQueue, queue_name: &str) -> Result<(), QueueError> {
+    let metrics = queue.metrics(queue_name).await?;
+    let optional = |value: Option<u64>| value.map_or("n/a".to_string(), |v| v.to_string());
+
+    println!("📊 {} ({} backend)", metrics.queue, metrics.backend);
+    println!("   Ready:     {}", optional(metrics.ready));
+    println!("   In flight: {}", metrics.in_flight);
+    println!("   Delayed:   {}", metrics.delayed);
+    println!("   Dead:      {}", metrics.dead);
+    if let Some(consumers) = metrics.consumers {
+        println!("   Consumers: {}", consumers);
+    }
+
+    Ok(())
+}
+
+async fn list(queue: &mut dyn JobQueue, queue_name: &str, limit: isize) -> Result<(), QueueError> {
+    let total = queue.dead_letter_count(queue_name).await?;
+    let letters = queue.list_dead_letters(queue_name, 0, limit).await?;
+
+    println!("☠️  {} dead letter(s) on {}", total, queue_name);
+
+    for letter in letters {
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        println!("🆔 {}", letter.id);
+        println!("🔁 Attempts: {}", letter.attempts);
+        println!("⏰ Failed at: {}", letter.failed_at);
+        println!("❌ Last error: {}", letter.last_error);
+        println!("📦 Payload: {}", letter.payload);
+    }
+
+    Ok(())
+}
+
+async fn scheduled(queue: &mut dyn JobQueue, queue_name: &str, limit: isize) -> Result<(), QueueError> {
+    let jobs = queue.list_scheduled(queue_name, limit).await?;
+
+    println!("⏳ {} scheduled job(s) on {}", jobs.len(), queue_name);
+
+    for job in jobs {
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        println!("🆔 {}", job.id);
+        println!("📋 Type: {}", job.job_type.as_deref().unwrap_or("unknown"));
+        println!("⏰ Runs at: {}", job.run_at);
+    }
+
+    Ok(())
+}
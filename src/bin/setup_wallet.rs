@@ -1,4 +1,4 @@
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::signature::Signer;
 use payment_gateway_rust::utils::wallet;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
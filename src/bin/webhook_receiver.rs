@@ -0,0 +1,88 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use payment_gateway_rust::services::webhooks::{
+    verify_signature, HEADER_EVENT_TYPE, HEADER_SIGNATURE,
+};
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+use tokio::net::TcpListener;
+
+/// Signatures older than this are rejected
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+#[derive(Clone)]
+struct ReceiverState {
+    secret: Option<String>,
+    /// Answer 500 to this many requests first (exercises retries)
+    fail_first: u32,
+    received: Arc<AtomicU32>,
+}
+
+/// Local stand-in for a merchant webhook endpoint
+/// Prints each delivery and checks its signature against WEBHOOK_SECRET
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    let addr = std::env::var("WEBHOOK_RECEIVER_ADDR").unwrap_or_else(|_| "127.0.0.1:4000".to_string());
+    let state = ReceiverState {
+        secret: std::env::var("WEBHOOK_SECRET").ok(),
+        fail_first: std::env::var("WEBHOOK_RECEIVER_FAIL_FIRST")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0),
+        received: Arc::new(AtomicU32::new(0)),
+    };
+
+    if state.secret.is_none() {
+        println!("⚠️  WEBHOOK_SECRET not set - signatures will not be verified");
+    }
+
+    let app = Router::new()
+        .route("/webhook", post(receive))
+        .with_state(state);
+
+    let listener = TcpListener::bind(&addr).await.unwrap();
+    println!("🪝 Webhook receiver listening on http://{}/webhook", addr);
+
+    axum::serve(listener, app).await.unwrap();
+}
+
+async fn receive(State(state): State<ReceiverState>, headers: HeaderMap, body: String) -> StatusCode {
+    let count = state.received.fetch_add(1, Ordering::SeqCst) + 1;
+    let event_type = headers
+        .get(HEADER_EVENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("unknown");
+
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("📨 Delivery #{} - {}", count, event_type);
+
+    if let Some(secret) = &state.secret {
+        let signature = headers
+            .get(HEADER_SIGNATURE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+
+        if !verify_signature(secret, signature, &body, SIGNATURE_TOLERANCE_SECS) {
+            println!("❌ Invalid signature: {}", signature);
+            return StatusCode::UNAUTHORIZED;
+        }
+        println!("🔐 Signature valid");
+    }
+
+    println!("{}", body);
+
+    if count <= state.fail_first {
+        println!("💥 Simulating failure ({}/{})", count, state.fail_first);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    StatusCode::OK
+}
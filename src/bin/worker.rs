@@ -1,10 +1,48 @@
-use payment_gateway_rust::{Config, Database, QueueService};
-use chrono::{DateTime, Utc};
-use uuid::Uuid;
+use payment_gateway_rust::{Config, Database, JobQueue, QueueService};
+use payment_gateway_rust::database::events::prune_events;
+use payment_gateway_rust::database::payments::{PaymentRepository, PgPaymentRepository};
+use payment_gateway_rust::database::scheduler::claim_scheduled_run;
+use payment_gateway_rust::indexer::SolanaIndexer;
+use payment_gateway_rust::services::email::ReceiptMailer;
+use payment_gateway_rust::services::jobs::{ExpirySweepJob, QueueJob, ReconciliationJob};
+use payment_gateway_rust::services::outbox::OutboxRelay;
+use payment_gateway_rust::services::queue;
+use payment_gateway_rust::services::webhooks::WebhookDispatcher;
+use payment_gateway_rust::utils::shutdown::shutdown_signal;
+use payment_gateway_rust::worker::{
+    ConfirmationHandler, EmailReceiptHandler, ExpirySweepHandler, HandlerRegistry, PaymentExpiryHandler,
+    ReconciliationHandler, WebhookDeliveryHandler,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+/// How often an expiry sweep job is queued - payments normally expire
+/// through their own scheduled job, the sweep only catches stragglers
+const EXPIRY_SWEEP_INTERVAL_SECS: u64 = 5 * 60;
+
+/// How often a reconciliation job is queued
+const RECONCILIATION_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Recent wallet signatures each reconciliation run re-checks
+const RECONCILIATION_SIGNATURE_LIMIT: usize = 50;
+
+/// Longest one pass over all queues blocks waiting for a job
+const QUEUE_BLOCK_TIMEOUT_SECS: f64 = 5.0;
+
+/// How often in-flight jobs are checked for expired leases
+const RECLAIM_INTERVAL_SECS: u64 = 15;
+
+/// How often delayed retries and scheduled jobs are checked for being due
+const PROMOTE_INTERVAL_SECS: u64 = 1;
+
+/// How often events past the retention period are deleted
+const EVENT_PRUNE_INTERVAL_SECS: u64 = 60 * 60;
 
 #[tokio::main]
 async fn main() {
-    println!("🔄 Starting Payment Confirmation Worker...\n");
+    println!("🔄 Starting Payment Worker...\n");
 
     // Load configuration
     let config = match Config::from_env() {
@@ -24,116 +62,288 @@ async fn main() {
         }
     };
 
-    // Connect to Redis queue
-    let mut queue = match QueueService::new(&config.redis_url).await {
+    // Connect to the job queue (Redis lists/streams or Postgres, per QUEUE_BACKEND)
+    let queue = match queue::connect(&config).await {
         Ok(q) => q,
         Err(e) => {
-            eprintln!("❌ Failed to connect to Redis: {}", e);
+            eprintln!("❌ Failed to connect to queue: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Live SSE/WebSocket updates go over Redis pub/sub when Redis is configured;
+    // without it the API streams re-read payment status from the database
+    let events = if config.uses_redis() {
+        match QueueService::new(&config.redis_url).await {
+            Ok(events) => Some(events),
+            Err(e) => {
+                eprintln!("⚠️  Redis unavailable, live payment updates disabled: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Outbound webhooks for payment status changes
+    let webhooks = match WebhookDispatcher::new(
+        db.pool.clone(),
+        Duration::from_secs(config.webhook_timeout_secs),
+        config.webhook_allow_private_hosts,
+    ) {
+        Ok(dispatcher) => dispatcher,
+        Err(e) => {
+            eprintln!("❌ Failed to create webhook client: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Receipt emails for settled payments
+    let mailer = match ReceiptMailer::new(config.receipt_email_url.clone(), config.receipt_email_from.clone()) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            eprintln!("❌ Failed to create email client: {}", e);
             std::process::exit(1);
         }
     };
 
+    let payments: Arc<dyn PaymentRepository> = Arc::new(PgPaymentRepository::new(db.pool.clone()));
+
+    let mut registry = HandlerRegistry::new()
+        .register(ConfirmationHandler {
+            payments: payments.clone(),
+            events: events.clone(),
+        })
+        .register(WebhookDeliveryHandler { webhooks })
+        .register(EmailReceiptHandler {
+            payments: payments.clone(),
+            mailer,
+        })
+        .register(PaymentExpiryHandler {
+            payments: payments.clone(),
+            events: events.clone(),
+        })
+        .register(ExpirySweepHandler {
+            payments: payments.clone(),
+            events: events.clone(),
+        });
+
+    // Reconciliation needs the wallet to watch
+    match std::env::var("WALLET_ADDRESS") {
+        Ok(wallet_address) => match SolanaIndexer::new(&config.solana_rpc_url, &wallet_address) {
+            Ok(indexer) => {
+                registry = registry.register(ReconciliationHandler {
+                    db: db.clone(),
+                    payments: payments.clone(),
+                    indexer: Arc::new(indexer),
+                    wallet_address,
+                });
+            }
+            Err(e) => eprintln!("⚠️  Reconciliation disabled, failed to create indexer: {}", e),
+        },
+        Err(_) => eprintln!("⚠️  WALLET_ADDRESS not set, reconciliation disabled"),
+    }
+
+    // WORKER_JOB_TYPES narrows this worker down to some job types
+    let registry = match registry.only(&config.worker_job_types) {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    let queues = registry.queues();
+
+    // Put jobs from crashed or hung workers, finished backoffs and due scheduled jobs on the queue
+    spawn_queue_maintenance(queue.clone_queue(), queues.clone());
+
+    // Every replica runs the schedulers; a claim in Postgres lets one of them queue each run
+    if registry.handles(ExpirySweepJob::JOB_TYPE) {
+        spawn_scheduler(db.clone(), queue.clone_queue(), EXPIRY_SWEEP_INTERVAL_SECS, ExpirySweepJob::default());
+    }
+    if registry.handles(ReconciliationJob::JOB_TYPE) {
+        let job = ReconciliationJob {
+            signature_limit: RECONCILIATION_SIGNATURE_LIMIT,
+        };
+        spawn_scheduler(db.clone(), queue.clone_queue(), RECONCILIATION_INTERVAL_SECS, job);
+    }
+
+    // Payment state changes queue their follow-up jobs (expiry, webhooks, receipts)
+    // through the outbox, as do reconciled payments - the indexer relays it too
+    OutboxRelay::new(db.pool.clone(), queue.clone_queue()).spawn();
+
+    if config.event_retention_days > 0 {
+        spawn_event_pruner(db.clone(), config.event_retention_days);
+    }
+
     println!("✅ Worker connected to database and queue!");
-    println!("👂 Listening for payment confirmation jobs...\n");
+    if config.queue_backend == "streams" {
+        println!("📋 Queue backend: streams (group {}, consumer {})", config.queue_consumer_group, config.queue_consumer_name);
+    } else {
+        println!("📋 Queue backend: {}", queue.backend_name());
+    }
+    println!("🧰 Job types: {}", registry.job_types().join(", "));
+    println!("👂 Listening on: {}\n", queues.join(", "));
+
+    // Each queue gets a share of the block timeout so none waits on the others
+    let timeout_secs = (QUEUE_BLOCK_TIMEOUT_SECS / queues.len() as f64).max(0.1);
+
+    // N slots, each reserving and handling one job at a time on its own queue handle
+    // (on Redis each handle blocks on its own connection, so slots never wait on each other)
+    let registry = Arc::new(registry);
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let mut slots = JoinSet::new();
+    for slot in 1..=config.worker_concurrency {
+        slots.spawn(run_slot(
+            slot,
+            registry.clone(),
+            queue.clone_queue(),
+            queues.clone(),
+            timeout_secs,
+            stop_rx.clone(),
+        ));
+    }
+    println!("⚙️  Concurrency: {} job(s) in flight\n", config.worker_concurrency);
 
-    // Main worker loop
+    shutdown_signal().await;
+    println!("\n🛑 Shutdown requested - no new jobs, finishing in-flight ones...");
+    let _ = stop_tx.send(true);
+
+    let drained = tokio::time::timeout(Duration::from_secs(config.shutdown_timeout_secs), async {
+        while slots.join_next().await.is_some() {}
+    })
+    .await;
+
+    if drained.is_err() {
+        // Their jobs stay reserved and are redelivered after the visibility timeout
+        eprintln!(
+            "⚠️  {} job(s) still running after {}s, abandoning them to be redelivered",
+            slots.len(),
+            config.shutdown_timeout_secs
+        );
+        slots.abort_all();
+    }
+
+    println!("👋 Worker stopped");
+}
+
+/// One worker slot: reserve a job, handle it, repeat until told to stop
+/// A job already reserved is always handled to the end; only waiting for one is cut short
+async fn run_slot(
+    slot: usize,
+    registry: Arc<HandlerRegistry>,
+    mut queue: Box<dyn JobQueue>,
+    queues: Vec<&'static str>,
+    timeout_secs: f64,
+    mut stop: watch::Receiver<bool>,
+) {
     loop {
-        // Pop confirmation job from queue (blocking)
-        match queue.pop_confirmation_job().await {
-            Ok(Some(job)) => {
-                println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                println!("📦 Processing Confirmation Job");
-                println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-
-                // Extract job data
-                let payment_id = job["payment_id"].as_str().unwrap_or("");
-                let memo = job["memo"].as_str().unwrap_or("");
-                let sender_address = job["sender_address"].as_str().unwrap_or("");
-                let tx_sig = job["tx_sig"].as_str().unwrap_or("");
-                let amount_lamports = job["amount_lamports"].as_i64().unwrap_or(0);
-                
-                // Parse paid_at timestamp
-                let paid_at: Option<DateTime<Utc>> = job["paid_at"].as_str()
-                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                    .map(|dt| dt.with_timezone(&Utc));
-
-                println!("💳 Payment ID: {}", payment_id);
-                println!("📝 Memo: {}", memo);
-                println!("👤 Sender: {}", sender_address);
-                println!("🔗 Signature: {}", tx_sig);
-                println!("💰 Amount: {} lamports", amount_lamports);
-                if let Some(pt) = paid_at {
-                    println!("⏰ Paid at: {}", pt);
-                }
+        for queue_name in &queues {
+            if *stop.borrow() {
+                return;
+            }
+
+            // Reserve a job (blocking) - it stays in flight until acked
+            // Dropping a reserve mid-call is safe: a job it had already claimed
+            // is reclaimed after the visibility timeout
+            let reserved = tokio::select! {
+                _ = stop.changed() => return,
+                reserved = queue.reserve(queue_name, timeout_secs) => reserved,
+            };
+
+            match reserved {
+                Ok(Some(reserved)) => {
+                    let job_type = reserved.envelope.as_ref().map(|envelope| envelope.job_type.as_str()).unwrap_or("unknown");
+                    println!("📦 [slot {}] Processing {} job {} (attempt {})", slot, job_type, reserved.id, reserved.attempts);
 
-                // Parse payment_id
-                let payment_uuid = match Uuid::parse_str(payment_id) {
-                    Ok(uuid) => uuid,
-                    Err(e) => {
-                        eprintln!("❌ Invalid payment_id: {}", e);
-                        continue;
+                    registry.process(queue.as_mut(), &reserved).await;
+                }
+                Ok(None) => {
+                    // Block timed out with no job - try the next queue
+                }
+                Err(e) => {
+                    eprintln!("❌ Queue error on {}: {}", queue_name, e);
+                    tokio::select! {
+                        _ = stop.changed() => return,
+                        _ = tokio::time::sleep(Duration::from_secs(5)) => {}
                     }
-                };
-
-                // Update payment request in database
-                match update_payment_confirmation(
-                    &db,
-                    payment_uuid,
-                    sender_address,
-                    tx_sig,
-                    paid_at,
-                ).await {
-                    Ok(_) => {
-                        println!("✅ Payment confirmed successfully!");
-                        println!("   Status: pending → confirmed");
-                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+                }
+            }
+        }
+    }
+}
+
+/// Requeue jobs whose visibility timeout has passed and
+/// promote delayed retries and scheduled jobs that are due
+fn spawn_queue_maintenance(mut queue: Box<dyn JobQueue>, queues: Vec<&'static str>) {
+    tokio::spawn(async move {
+        let mut reclaim = tokio::time::interval(Duration::from_secs(RECLAIM_INTERVAL_SECS));
+        let mut promote = tokio::time::interval(Duration::from_secs(PROMOTE_INTERVAL_SECS));
+
+        loop {
+            tokio::select! {
+                _ = reclaim.tick() => {
+                    for queue_name in &queues {
+                        if let Err(e) = queue.reclaim_stale_jobs(queue_name).await {
+                            eprintln!("❌ Job reclaim failed on {}: {}", queue_name, e);
+                        }
                     }
-                    Err(e) => {
-                        eprintln!("❌ Failed to update payment: {}", e);
-                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+                }
+                _ = promote.tick() => {
+                    for queue_name in &queues {
+                        if let Err(e) = queue.promote_delayed_jobs(queue_name).await {
+                            eprintln!("❌ Delayed job promotion failed on {}: {}", queue_name, e);
+                        }
                     }
                 }
             }
-            Ok(None) => {
-                // No job available (shouldn't happen with blocking pop)
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        }
+    });
+}
+
+/// Queue a periodic job every `interval_secs`, once across all worker replicas
+fn spawn_scheduler<J: QueueJob + Send + Sync + 'static>(
+    db: Database,
+    mut queue: Box<dyn JobQueue>,
+    interval_secs: u64,
+    job: J,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            match claim_scheduled_run(&db.pool, J::JOB_TYPE, interval_secs).await {
+                Ok(true) => {}
+                // Another replica queued this run
+                Ok(false) => continue,
+                Err(e) => {
+                    eprintln!("❌ Failed to claim {} run: {}", J::JOB_TYPE, e);
+                    continue;
+                }
             }
-            Err(e) => {
-                eprintln!("❌ Queue error: {}", e);
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+
+            if let Err(e) = queue.push_job(&job).await {
+                eprintln!("❌ Failed to schedule {} job: {}", J::JOB_TYPE, e);
             }
         }
-    }
+    });
 }
 
-/// Update payment request with confirmation details
-async fn update_payment_confirmation(
-    db: &Database,
-    payment_id: Uuid,
-    sender_address: &str,
-    tx_sig: &str,
-    paid_at: Option<DateTime<Utc>>,
-) -> Result<(), sqlx::Error> {
-    let now = Utc::now();
-
-    sqlx::query(
-        r#"
-        UPDATE payment_requests
-        SET status = 'confirmed',
-            sender_address = $2,
-            tx_sig = $3,
-            paid_at = $4,
-            updated_at = $5
-        WHERE id = $1
-        "#,
-    )
-    .bind(payment_id)
-    .bind(sender_address)
-    .bind(tx_sig)
-    .bind(paid_at)
-    .bind(now)
-    .execute(&db.pool)
-    .await?;
-
-    Ok(())
+/// Periodically delete events older than the retention period
+fn spawn_event_pruner(db: Database, retention_days: i64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(EVENT_PRUNE_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            match prune_events(&db.pool, retention_days).await {
+                Ok(0) => {}
+                Ok(count) => println!("🧹 Pruned {} events older than {} days", count, retention_days),
+                Err(e) => eprintln!("❌ Event pruning failed: {}", e),
+            }
+        }
+    });
 }
@@ -1,4 +1,10 @@
+pub mod tokens;
+
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
+
+use crate::services::retry::RetryPolicy;
 
 /// Configuration struct - holds all environment variables
 #[derive(Debug, Clone)]
@@ -6,12 +12,62 @@ pub struct Config {
     pub server_host: String,
     pub server_port: u16,
     pub database_url: String,
+    /// Apply pending migrations when the API server starts
+    pub migrate_on_startup: bool,
     pub redis_url: String,
+    /// REDIS_URL was set, rather than defaulting to localhost
+    pub redis_url_set: bool,
     pub solana_rpc_url: String,
     pub jwt_secret: String,
+    /// Reject a second payment with the same order_id for the same merchant
+    pub order_id_unique: bool,
+    /// Merchant name shown in wallets (Solana Pay label)
+    pub merchant_label: String,
+    /// Optional PNG logo overlaid on QR codes
+    pub merchant_logo_path: Option<String>,
+    /// Absolute https URL of the merchant icon shown in wallets
+    pub merchant_icon_url: Option<String>,
+    /// Keypair file of the hot wallet that pays fees for sponsored checkouts
+    pub fee_payer_keypair_path: Option<String>,
+    /// "off", "spl" (token payments only) or "all"
+    pub fee_sponsor_mode: String,
+    /// Max sponsored fees per merchant per rolling 24h
+    pub fee_sponsor_daily_limit_lamports: i64,
+    /// Per-request timeout for webhook deliveries
+    pub webhook_timeout_secs: u64,
+    /// Allow webhook URLs on loopback/private networks (local development only)
+    pub webhook_allow_private_hosts: bool,
+    /// Days events stay in the /events feed (0 keeps them forever)
+    pub event_retention_days: i64,
+    /// Seconds a reserved queue job stays invisible before it is reclaimed
+    pub queue_visibility_timeout_secs: u64,
+    /// Retries before dead-lettering, with exponential backoff, per queue
+    /// (see `retry_policy_from`)
+    pub retry_policies: HashMap<String, RetryPolicy>,
+    /// Job queue backend: "list" or "streams" (Redis) or "postgres"
+    /// (`MemoryQueue` is only for tests - separate processes can't share it)
+    pub queue_backend: String,
+    pub queue_consumer_group: String,
+    /// Unique per worker replica on the streams backend
+    pub queue_consumer_name: String,
+    /// Job types this worker handles (empty = all registered types)
+    pub worker_job_types: Vec<String>,
+    /// Jobs a worker process handles at once
+    pub worker_concurrency: usize,
+    /// How long a stopping worker (or API server) waits for in-flight work
+    pub shutdown_timeout_secs: u64,
+    /// HTTP email API receipts are posted to; unset logs receipts instead
+    pub receipt_email_url: Option<String>,
+    pub receipt_email_from: String,
 }
 
 impl Config {
+    /// Whether Redis is part of this deployment - the list and streams queues
+    /// need it; with the postgres queue it is only used when REDIS_URL is set
+    pub fn uses_redis(&self) -> bool {
+        self.queue_backend != "postgres" || self.redis_url_set
+    }
+
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self, String> {
         // Load .env file
@@ -28,15 +84,191 @@ impl Config {
             
             database_url: env::var("DATABASE_URL")
                 .map_err(|_| "DATABASE_URL must be set")?,
+
+            migrate_on_startup: env::var("MIGRATE_ON_STARTUP")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .map_err(|_| "Invalid MIGRATE_ON_STARTUP (expected true or false)")?,
             
             redis_url: env::var("REDIS_URL")
                 .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+
+            redis_url_set: env::var("REDIS_URL").is_ok(),
             
             solana_rpc_url: env::var("SOLANA_RPC_URL")
                 .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
             
             jwt_secret: env::var("JWT_SECRET")
                 .map_err(|_| "JWT_SECRET must be set")?,
+
+            order_id_unique: env::var("ORDER_ID_UNIQUE")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|_| "Invalid ORDER_ID_UNIQUE (expected true or false)")?,
+
+            merchant_label: env::var("MERCHANT_LABEL")
+                .unwrap_or_else(|_| "Solana Payment Gateway".to_string()),
+
+            merchant_logo_path: env::var("MERCHANT_LOGO_PATH").ok(),
+
+            merchant_icon_url: env::var("MERCHANT_ICON_URL").ok(),
+
+            fee_payer_keypair_path: env::var("FEE_PAYER_KEYPAIR_PATH").ok(),
+
+            fee_sponsor_mode: env::var("FEE_SPONSOR_MODE")
+                .unwrap_or_else(|_| "off".to_string()),
+
+            fee_sponsor_daily_limit_lamports: env::var("FEE_SPONSOR_DAILY_LIMIT_LAMPORTS")
+                .unwrap_or_else(|_| "10000000".to_string())
+                .parse()
+                .map_err(|_| "Invalid FEE_SPONSOR_DAILY_LIMIT_LAMPORTS")?,
+
+            webhook_timeout_secs: env::var("WEBHOOK_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .map_err(|_| "Invalid WEBHOOK_TIMEOUT_SECS")?,
+
+            webhook_allow_private_hosts: env::var("WEBHOOK_ALLOW_PRIVATE_HOSTS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|_| "Invalid WEBHOOK_ALLOW_PRIVATE_HOSTS (expected true or false)")?,
+
+            event_retention_days: env::var("EVENT_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| "Invalid EVENT_RETENTION_DAYS")?,
+
+            queue_visibility_timeout_secs: env::var("QUEUE_VISIBILITY_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .map_err(|_| "Invalid QUEUE_VISIBILITY_TIMEOUT_SECS")?,
+
+            retry_policies: crate::services::queue::QUEUES
+                .iter()
+                .map(|queue| Ok((queue.to_string(), retry_policy_from(queue, |name| env::var(name).ok())?)))
+                .collect::<Result<_, String>>()?,
+
+            queue_backend: match env::var("QUEUE_BACKEND")
+                .unwrap_or_else(|_| "list".to_string())
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                backend @ ("list" | "streams" | "postgres") => backend.to_string(),
+                "memory" => return Err(
+                    "QUEUE_BACKEND=memory is not shared between the API, indexer and worker processes (expected list, streams or postgres)"
+                        .to_string(),
+                ),
+                _ => return Err("Invalid QUEUE_BACKEND (expected list, streams or postgres)".to_string()),
+            },
+
+            queue_consumer_group: env::var("QUEUE_CONSUMER_GROUP")
+                .unwrap_or_else(|_| crate::services::queue::DEFAULT_CONSUMER_GROUP.to_string()),
+
+            queue_consumer_name: env::var("QUEUE_CONSUMER_NAME").unwrap_or_else(|_| {
+                let host = env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string());
+                format!("{}-{}", host, std::process::id())
+            }),
+
+            worker_job_types: env::var("WORKER_JOB_TYPES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|job_type| job_type.trim().to_ascii_lowercase())
+                .filter(|job_type| !job_type.is_empty())
+                .collect(),
+
+            worker_concurrency: env::var("WORKER_CONCURRENCY")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse::<usize>()
+                .map_err(|_| "Invalid WORKER_CONCURRENCY")?
+                .max(1),
+
+            shutdown_timeout_secs: env::var("SHUTDOWN_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| "Invalid SHUTDOWN_TIMEOUT_SECS")?,
+
+            receipt_email_url: env::var("RECEIPT_EMAIL_URL").ok().filter(|url| !url.is_empty()),
+
+            receipt_email_from: env::var("RECEIPT_EMAIL_FROM")
+                .unwrap_or_else(|_| "receipts@localhost".to_string()),
         })
     }
-}     
+}
+
+/// Retry policy for one queue from `<NAME>_MAX_ATTEMPTS`, `<NAME>_RETRY_BASE_SECS`
+/// and `<NAME>_RETRY_MAX_SECS`, where NAME is the queue without `_queue`
+/// (e.g. EMAIL_MAX_ATTEMPTS for email_queue); unset values keep the default
+fn retry_policy_from(queue: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<RetryPolicy, String> {
+    let prefix = queue.trim_end_matches("_queue").to_ascii_uppercase();
+    let mut policy = match queue {
+        // Confirmations are retried sooner - the RPC usually catches up quickly
+        crate::services::queue::CONFIRMATION_QUEUE => RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(300),
+        },
+        _ => RetryPolicy::default(),
+    };
+
+    let var = |suffix: &str| -> Result<Option<u64>, String> {
+        let name = format!("{}_{}", prefix, suffix);
+        lookup(&name)
+            .map(|value| value.trim().parse().map_err(|_| format!("Invalid {}", name)))
+            .transpose()
+    };
+
+    if let Some(max_attempts) = var("MAX_ATTEMPTS")? {
+        policy.max_attempts = u32::try_from(max_attempts).unwrap_or(u32::MAX).max(1);
+    }
+    if let Some(secs) = var("RETRY_BASE_SECS")? {
+        policy.base_delay = Duration::from_secs(secs);
+    }
+    if let Some(secs) = var("RETRY_MAX_SECS")? {
+        policy.max_delay = Duration::from_secs(secs);
+    }
+
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::queue::{CONFIRMATION_QUEUE, EMAIL_QUEUE, PAYMENT_EXPIRY_QUEUE};
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+    }
+
+    #[test]
+    fn retry_policy_is_read_per_queue() {
+        let vars = [
+            ("EMAIL_MAX_ATTEMPTS", "9"),
+            ("EMAIL_RETRY_BASE_SECS", "30"),
+            ("PAYMENT_EXPIRY_RETRY_MAX_SECS", "60"),
+        ];
+
+        let email = retry_policy_from(EMAIL_QUEUE, lookup(&vars)).unwrap();
+        assert_eq!(email.max_attempts, 9);
+        assert_eq!(email.base_delay, Duration::from_secs(30));
+        assert_eq!(email.max_delay, RetryPolicy::default().max_delay);
+
+        let expiry = retry_policy_from(PAYMENT_EXPIRY_QUEUE, lookup(&vars)).unwrap();
+        assert_eq!(expiry.max_attempts, RetryPolicy::default().max_attempts);
+        assert_eq!(expiry.max_delay, Duration::from_secs(60));
+
+        // Confirmation keeps its own defaults
+        let confirmation = retry_policy_from(CONFIRMATION_QUEUE, lookup(&[])).unwrap();
+        assert_eq!(confirmation.max_attempts, 5);
+        assert_eq!(confirmation.base_delay, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn retry_policy_rejects_bad_values() {
+        assert_eq!(
+            retry_policy_from(EMAIL_QUEUE, lookup(&[("EMAIL_MAX_ATTEMPTS", "lots")])).unwrap_err(),
+            "Invalid EMAIL_MAX_ATTEMPTS"
+        );
+        let policy = retry_policy_from(EMAIL_QUEUE, lookup(&[("EMAIL_MAX_ATTEMPTS", "0")])).unwrap();
+        assert_eq!(policy.max_attempts, 1);
+    }
+}
@@ -0,0 +1,41 @@
+/// Token accepted by the gateway
+/// Amounts are always expressed in the token's base units (lamports for SOL)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub symbol: &'static str,
+    /// SPL mint address (None for native SOL)
+    pub mint: Option<&'static str>,
+    pub decimals: u8,
+    pub min_amount: i64,
+    pub max_amount: i64,
+}
+
+/// Tokens the gateway knows how to price and match
+pub const SUPPORTED_TOKENS: &[TokenInfo] = &[
+    TokenInfo {
+        symbol: "SOL",
+        mint: None,
+        decimals: 9,
+        min_amount: 5_000,                 // 0.000005 SOL (one signature fee)
+        max_amount: 1_000_000_000_000,     // 1,000 SOL
+    },
+    TokenInfo {
+        symbol: "USDC",
+        mint: Some("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+        decimals: 6,
+        min_amount: 10_000,                // 0.01 USDC
+        max_amount: 1_000_000_000_000,     // 1,000,000 USDC
+    },
+];
+
+/// Look up a supported token by symbol (case-insensitive)
+pub fn find_token(symbol: &str) -> Option<&'static TokenInfo> {
+    SUPPORTED_TOKENS
+        .iter()
+        .find(|token| token.symbol.eq_ignore_ascii_case(symbol))
+}
+
+/// Look up a supported SPL token by mint address
+pub fn find_token_by_mint(mint: &str) -> Option<&'static TokenInfo> {
+    SUPPORTED_TOKENS.iter().find(|token| token.mint == Some(mint))
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::events::record_event;
+use super::fees::settle_sponsored_fee;
+use super::models::PaymentRequest;
+use super::outbox::enqueue_job;
+use crate::services::jobs::{EmailReceiptJob, PaymentConfirmationJob};
+use crate::services::webhooks::{
+    payment_webhook_event, WebhookEvent, EVENT_PAYMENT_CONFIRMED, EVENT_PAYMENT_UNDERPAID,
+};
+
+/// Unique index that stops one signature settling two payments
+pub const TX_SIG_UNIQUE_INDEX: &str = "idx_tx_sig_unique";
+
+/// Result of applying a confirmation job
+#[derive(Debug)]
+pub enum ConfirmOutcome {
+    /// Payment moved out of pending by this signature
+    Updated {
+        payment: PaymentRequest,
+        event: WebhookEvent,
+    },
+    /// Already settled by this same signature (a redelivered job) - nothing changed
+    AlreadyConfirmed(PaymentRequest),
+    /// Refused and written to confirmation_conflicts - nothing changed
+    Conflict(ConfirmationConflict),
+    /// No payment with the job's payment id
+    NotFound,
+}
+
+/// A signature that tried to settle a payment it can't (matches confirmation_conflicts table)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ConfirmationConflict {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub memo: String,
+    pub tx_sig: String,
+    pub sender_address: String,
+    pub amount_lamports: i64,
+    /// Payment status and signature when the conflicting signature arrived
+    pub existing_status: Option<String>,
+    pub existing_tx_sig: Option<String>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Settle a pending payment from a confirmation job, exactly once
+/// Only a pending row paid in its own token is updated; payments that received
+/// less than requested are marked underpaid. The matching event, its webhook
+/// deliveries, the receipt email job and the settled sponsored fee are written
+/// in the same transaction
+pub async fn confirm_payment(pool: &PgPool, job: &PaymentConfirmationJob) -> Result<ConfirmOutcome, sqlx::Error> {
+    match try_confirm(pool, job).await {
+        // The signature already settled a different payment
+        Err(sqlx::Error::Database(db_err)) if db_err.constraint() == Some(TX_SIG_UNIQUE_INDEX) => {
+            let mut tx = pool.begin().await?;
+            let existing = find_payment(&mut tx, job.payment_id).await?;
+            let (status, tx_sig) = existing
+                .map(|payment| (Some(payment.status), payment.tx_sig))
+                .unwrap_or_default();
+
+            let conflict = record_conflict(&mut tx, job, status, tx_sig, "Signature already settled another payment").await?;
+            tx.commit().await?;
+
+            Ok(ConfirmOutcome::Conflict(conflict))
+        }
+        other => other,
+    }
+}
+
+async fn try_confirm(pool: &PgPool, job: &PaymentConfirmationJob) -> Result<ConfirmOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query_as::<_, PaymentRequest>(
+        r#"
+        UPDATE payment_requests
+        SET status = CASE
+                WHEN $6 <= 0 OR $6 < amount_lamports THEN 'underpaid'
+                ELSE 'confirmed'
+            END,
+            sender_address = $2,
+            tx_sig = $3,
+            paid_at = $4,
+            updated_at = $5
+        WHERE id = $1 AND status = 'pending' AND token_symbol = $7
+        RETURNING *
+        "#,
+    )
+    .bind(job.payment_id)
+    .bind(&job.sender_address)
+    .bind(&job.tx_sig)
+    .bind(job.paid_at)
+    .bind(Utc::now())
+    .bind(job.amount_lamports)
+    .bind(&job.token_symbol)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(payment) = updated {
+        let event_type = if payment.status == "underpaid" {
+            EVENT_PAYMENT_UNDERPAID
+        } else {
+            EVENT_PAYMENT_CONFIRMED
+        };
+        let event = payment_webhook_event(event_type, &payment);
+        record_event(&mut tx, &payment.receiver_address, Some(payment.id), &event).await?;
+        settle_sponsored_fee(&mut tx, payment.id, &job.fee_payer, job.fee_lamports).await?;
+        if payment.customer_email.is_some() {
+            enqueue_job(&mut tx, &EmailReceiptJob { payment_id: payment.id }).await?;
+        }
+
+        tx.commit().await?;
+
+        return Ok(ConfirmOutcome::Updated { payment, event });
+    }
+
+    // Not pending or paid in another token - find out why
+    let Some(payment) = find_payment(&mut tx, job.payment_id).await? else {
+        return Ok(ConfirmOutcome::NotFound);
+    };
+
+    if payment.tx_sig.as_deref() == Some(job.tx_sig.as_str()) {
+        return Ok(ConfirmOutcome::AlreadyConfirmed(payment));
+    }
+
+    let reason = conflict_reason(job, &payment);
+    let conflict = record_conflict(&mut tx, job, Some(payment.status), payment.tx_sig, &reason).await?;
+    tx.commit().await?;
+
+    Ok(ConfirmOutcome::Conflict(conflict))
+}
+
+/// Why a job can't settle a payment it didn't update
+pub fn conflict_reason(job: &PaymentConfirmationJob, payment: &PaymentRequest) -> String {
+    if payment.token_symbol != job.token_symbol {
+        return format!("Paid in {}, payment expects {}", job.token_symbol, payment.token_symbol);
+    }
+
+    match &payment.tx_sig {
+        Some(_) => format!("Payment already {} by another signature", payment.status),
+        None => format!("Payment is {}", payment.status),
+    }
+}
+
+/// Current row, locked so the outcome reflects a settled state
+async fn find_payment(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_id: Uuid,
+) -> Result<Option<PaymentRequest>, sqlx::Error> {
+    sqlx::query_as::<_, PaymentRequest>("SELECT * FROM payment_requests WHERE id = $1 FOR UPDATE")
+        .bind(payment_id)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+/// Log a refused signature, once per payment and signature (jobs get redelivered)
+async fn record_conflict(
+    tx: &mut Transaction<'_, Postgres>,
+    job: &PaymentConfirmationJob,
+    existing_status: Option<String>,
+    existing_tx_sig: Option<String>,
+    reason: &str,
+) -> Result<ConfirmationConflict, sqlx::Error> {
+    sqlx::query_as::<_, ConfirmationConflict>(
+        r#"
+        INSERT INTO confirmation_conflicts
+            (id, payment_id, memo, tx_sig, sender_address, amount_lamports, existing_status, existing_tx_sig, reason)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (payment_id, tx_sig) DO UPDATE
+        SET existing_status = EXCLUDED.existing_status,
+            existing_tx_sig = EXCLUDED.existing_tx_sig,
+            reason = EXCLUDED.reason
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(job.payment_id)
+    .bind(&job.memo)
+    .bind(&job.tx_sig)
+    .bind(&job.sender_address)
+    .bind(job.amount_lamports)
+    .bind(existing_status)
+    .bind(existing_tx_sig)
+    .bind(reason)
+    .fetch_one(&mut **tx)
+    .await
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::outbox::enqueue_webhooks;
+use crate::services::webhooks::WebhookEvent;
+
+/// Stored payment event (matches events table)
+/// `(tx_id, seq)` gives the feed its order; `id` matches the webhook event id
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StoredEvent {
+    /// Id of the transaction that wrote the event
+    #[serde(skip_serializing)]
+    pub tx_id: i64,
+    #[serde(skip_serializing)]
+    pub seq: i64,
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub payment_id: Option<Uuid>,
+    pub data: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Append an event and queue its webhook deliveries (through the outbox)
+/// inside the transaction that made the state change
+pub async fn record_event(
+    tx: &mut Transaction<'_, Postgres>,
+    merchant_address: &str,
+    payment_id: Option<Uuid>,
+    event: &WebhookEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO events (id, event_type, merchant_address, payment_id, data, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(event.id)
+    .bind(&event.event_type)
+    .bind(merchant_address)
+    .bind(payment_id)
+    .bind(&event.data)
+    .bind(event.created_at)
+    .execute(&mut **tx)
+    .await?;
+
+    enqueue_webhooks(tx, merchant_address, event).await
+}
+
+/// Events for a merchant after a (tx_id, seq) position, oldest first
+/// Only events from transactions older than every running one are returned:
+/// anything committed later sorts after them, so a cursor never skips a row
+pub async fn events_after(
+    pool: &PgPool,
+    merchant_address: &str,
+    after_tx_id: i64,
+    after_seq: i64,
+    event_types: &[String],
+    limit: i64,
+) -> Result<Vec<StoredEvent>, sqlx::Error> {
+    sqlx::query_as::<_, StoredEvent>(
+        r#"
+        SELECT tx_id::TEXT::BIGINT AS tx_id, seq, id, event_type, payment_id, data, created_at
+        FROM events
+        WHERE merchant_address = $1
+          AND (tx_id, seq) > ($2::BIGINT::TEXT::XID8, $3)
+          AND tx_id < pg_snapshot_xmin(pg_current_snapshot())
+          AND (cardinality($4::TEXT[]) = 0 OR event_type = ANY($4))
+        ORDER BY tx_id ASC, seq ASC
+        LIMIT $5
+        "#,
+    )
+    .bind(merchant_address)
+    .bind(after_tx_id)
+    .bind(after_seq)
+    .bind(event_types)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete events older than the retention period, returning how many were removed
+pub async fn prune_events(pool: &PgPool, retention_days: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM events WHERE created_at < NOW() - make_interval(days => $1)")
+        .bind(retention_days as i32)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Sponsored fee totals for one merchant
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SponsoredFeeSummary {
+    pub merchant_address: String,
+    /// Reserved and settled fees in the last 24h (what the daily limit counts)
+    pub last_24h_lamports: i64,
+    /// Reserved for transactions that haven't landed yet
+    pub reserved_lamports: i64,
+    pub total_lamports: i64,
+    pub transaction_count: i64,
+    pub last_sponsored_at: Option<DateTime<Utc>>,
+}
+
+/// A signed sponsored transaction whose fee is to be reserved
+#[derive(Debug, Clone)]
+pub struct NewSponsoredFee<'a> {
+    pub payment_id: Uuid,
+    pub merchant_address: &'a str,
+    pub fee_payer: &'a str,
+    pub customer_account: &'a str,
+    pub fee_lamports: i64,
+    /// Fee payer signature - the transaction's id on-chain
+    pub tx_sig: &'a str,
+    /// Base64 wire format, as returned to the wallet
+    pub signed_transaction: &'a str,
+}
+
+/// The sponsored transaction already issued for a payment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SponsoredFeeReservation {
+    pub customer_account: String,
+    pub status: String,
+    pub signed_transaction: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Reservation for a payment, if a sponsored transaction was issued for it
+pub async fn find_sponsored_fee(
+    pool: &PgPool,
+    payment_id: Uuid,
+) -> Result<Option<SponsoredFeeReservation>, sqlx::Error> {
+    sqlx::query_as::<_, SponsoredFeeReservation>(
+        r#"
+        SELECT customer_account, status, signed_transaction, created_at
+        FROM sponsored_fees
+        WHERE payment_id = $1
+        "#,
+    )
+    .bind(payment_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Reserve the fee of a payment's sponsored transaction if it fits in the
+/// merchant's rolling 24h limit
+/// A payment gets at most one reservation, and so one signed transaction.
+/// Returns false (and reserves nothing) when the limit would be exceeded or
+/// the payment already has one
+pub async fn reserve_sponsored_fee(
+    pool: &PgPool,
+    fee: &NewSponsoredFee<'_>,
+    daily_limit_lamports: i64,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // Serialize reservations per merchant so concurrent requests can't overshoot
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(fee.merchant_address)
+        .execute(&mut *tx)
+        .await?;
+
+    let (spent,): (i64,) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(fee_lamports), 0)::BIGINT FROM sponsored_fees
+        WHERE merchant_address = $1 AND status <> 'released'
+          AND created_at > NOW() - INTERVAL '24 hours'
+        "#,
+    )
+    .bind(fee.merchant_address)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if spent + fee.fee_lamports > daily_limit_lamports {
+        return Ok(false);
+    }
+
+    let reserved = sqlx::query(
+        r#"
+        INSERT INTO sponsored_fees
+        (id, payment_id, merchant_address, fee_payer, customer_account, fee_lamports, tx_sig, signed_transaction)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (payment_id) DO NOTHING
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(fee.payment_id)
+    .bind(fee.merchant_address)
+    .bind(fee.fee_payer)
+    .bind(fee.customer_account)
+    .bind(fee.fee_lamports)
+    .bind(fee.tx_sig)
+    .bind(fee.signed_transaction)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(reserved.rows_affected() > 0)
+}
+
+/// Settle a payment's reservation once its transaction landed
+/// The real fee is recorded if our fee payer paid it; otherwise the customer
+/// paid their own fee (e.g. used the transfer URL) and the reservation is released
+pub async fn settle_sponsored_fee(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_id: Uuid,
+    fee_payer: &str,
+    fee_lamports: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE sponsored_fees
+        SET status = CASE WHEN fee_payer = $2 THEN 'settled' ELSE 'released' END,
+            fee_lamports = CASE WHEN fee_payer = $2 THEN $3 ELSE fee_lamports END,
+            settled_at = NOW()
+        WHERE payment_id = $1 AND status = 'reserved'
+        "#,
+    )
+    .bind(payment_id)
+    .bind(fee_payer)
+    .bind(fee_lamports)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Settle the fee of a sponsored transaction that failed on-chain
+/// The fee payer was charged even though nothing was paid, so the fee counts
+/// against the limit - also when the payment already expired and released it
+pub async fn settle_failed_sponsored_fee(
+    pool: &PgPool,
+    tx_sig: &str,
+    fee_lamports: i64,
+) -> Result<bool, sqlx::Error> {
+    let settled = sqlx::query(
+        r#"
+        UPDATE sponsored_fees
+        SET status = 'settled', fee_lamports = $2, settled_at = NOW()
+        WHERE tx_sig = $1 AND status <> 'settled'
+        "#,
+    )
+    .bind(tx_sig)
+    .bind(fee_lamports)
+    .execute(pool)
+    .await?;
+
+    Ok(settled.rows_affected() > 0)
+}
+
+/// Give back the reservations of payments that expired unpaid
+pub async fn release_sponsored_fees(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_ids: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE sponsored_fees
+        SET status = 'released', settled_at = NOW()
+        WHERE payment_id = ANY($1) AND status = 'reserved'
+        "#,
+    )
+    .bind(payment_ids)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Sponsored fee totals for a merchant
+pub async fn sponsored_fee_summary(
+    pool: &PgPool,
+    merchant_address: &str,
+) -> Result<SponsoredFeeSummary, sqlx::Error> {
+    sqlx::query_as::<_, SponsoredFeeSummary>(
+        r#"
+        SELECT
+            $1 AS merchant_address,
+            COALESCE(SUM(fee_lamports) FILTER (WHERE created_at > NOW() - INTERVAL '24 hours'), 0)::BIGINT AS last_24h_lamports,
+            COALESCE(SUM(fee_lamports) FILTER (WHERE status = 'reserved'), 0)::BIGINT AS reserved_lamports,
+            COALESCE(SUM(fee_lamports), 0)::BIGINT AS total_lamports,
+            COUNT(*) AS transaction_count,
+            MAX(created_at) AS last_sponsored_at
+        FROM sponsored_fees
+        WHERE merchant_address = $1 AND status <> 'released'
+        "#,
+    )
+    .bind(merchant_address)
+    .fetch_one(pool)
+    .await
+}
@@ -1,8 +1,29 @@
+pub mod confirmations;
+pub mod events;
+pub mod fees;
 pub mod models;
+pub mod outbox;
+pub mod payments;
+pub mod scheduler;
 
+use chrono::{DateTime, Utc};
+use sqlx::migrate::{MigrateError, Migrator};
 use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::collections::HashMap;
 use std::time::Duration;
 
+/// Schema migrations, embedded at compile time
+/// Never edit an applied migration - its checksum is verified; add a new file instead
+pub static MIGRATOR: Migrator = sqlx::migrate!("./database/migrations");
+
+/// One embedded migration and whether it has been applied
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
 /// Database connection pool
 #[derive(Clone)]
 pub struct Database {
@@ -23,49 +44,41 @@ impl Database {
         Ok(Database { pool })
     }
 
-    /// Initialize database (create tables)
-    pub async fn init(&self) -> Result<(), sqlx::Error> {
-        // Create payment_requests table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS payment_requests (
-                id UUID PRIMARY KEY,
-                amount_lamports BIGINT NOT NULL,
-                token_symbol TEXT DEFAULT 'SOL',
-                memo TEXT UNIQUE NOT NULL,
-                status TEXT NOT NULL DEFAULT 'pending',
-                receiver_address TEXT NOT NULL,
-                sender_address TEXT,
-                tx_sig TEXT,
-                block_height BIGINT,
-                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                paid_at TIMESTAMPTZ,
-                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                expires_at TIMESTAMPTZ,
-                order_id TEXT,
-                customer_email TEXT,
-                metadata JSONB
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+    /// Apply pending migrations from `database/migrations`
+    /// Applied versions are recorded in `_sqlx_migrations`; each runs once, in order
+    pub async fn migrate(&self) -> Result<(), MigrateError> {
+        MIGRATOR.run(&self.pool).await?;
 
-        // Create indexes
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_memo ON payment_requests(memo)")
-            .execute(&self.pool)
-            .await?;
-        
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_status ON payment_requests(status)")
-            .execute(&self.pool)
-            .await?;
-        
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tx_sig ON payment_requests(tx_sig)")
-            .execute(&self.pool)
+        println!("✅ Database migrations up to date!");
+
+        Ok(())
+    }
+
+    /// Every embedded migration and when it was applied (None = pending)
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+        // No tracking table until the first migration run
+        let (tracked,) = sqlx::query_as::<_, (bool,)>("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&self.pool)
             .await?;
 
-        println!("✅ Payment_requests table created/verified!");
+        let applied: HashMap<i64, DateTime<Utc>> = if tracked {
+            sqlx::query_as::<_, (i64, DateTime<Utc>)>("SELECT version, installed_on FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .collect()
+        } else {
+            HashMap::new()
+        };
 
-        Ok(())
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied_at: applied.get(&migration.version).copied(),
+            })
+            .collect())
     }
 }
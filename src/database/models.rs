@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::payments::PaymentFilter;
+
 /// Payment Request model (matches database table)
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct PaymentRequest {
@@ -20,6 +22,8 @@ pub struct PaymentRequest {
     pub expires_at: Option<DateTime<Utc>>,
     pub order_id: Option<String>,
     pub customer_email: Option<String>,
+    /// Solana Pay reference public key
+    pub reference: Option<String>,
 }
 
 /// Create payment request body
@@ -39,6 +43,8 @@ pub struct PaymentResponse {
     pub token_symbol: String,
     pub receiver_address: String,
     pub memo: String,
+    pub reference: String,
+    pub solana_pay_url: String,
     pub instructions: String,
     pub status: String,
     pub created_at: DateTime<Utc>,
@@ -56,6 +62,8 @@ pub struct PaymentStatusResponse {
     pub sender_address: Option<String>,
     pub receiver_address: String,
     pub tx_sig: Option<String>,
+    pub order_id: Option<String>,
+    pub solana_pay_url: Option<String>,
     pub paid_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
@@ -71,14 +79,100 @@ impl From<PaymentRequest> for PaymentStatusResponse {
             sender_address: payment.sender_address,
             receiver_address: payment.receiver_address,
             tx_sig: payment.tx_sig,
+            order_id: payment.order_id,
+            solana_pay_url: None,
             paid_at: payment.paid_at,
             created_at: payment.created_at,
         }
     }
 }
 
+impl PaymentStatusResponse {
+    /// Attach the Solana Pay URL built by the API
+    pub fn with_solana_pay_url(mut self, url: Option<String>) -> Self {
+        self.solana_pay_url = url;
+        self
+    }
+}
+
 /// Generate unique memo
 pub fn generate_memo() -> String {
     let uuid = Uuid::new_v4().to_string();
     format!("PAY-{}", &uuid[..8].to_uppercase())
 }
+
+/// Query parameters for GET /payments/:id
+#[derive(Debug, Default, Deserialize)]
+pub struct PaymentStatusQuery {
+    /// Long-poll: hold the request up to this many seconds until the status changes
+    pub wait: Option<u64>,
+}
+
+/// Body for POST /payments/status
+#[derive(Debug, Deserialize)]
+pub struct BatchStatusRequest {
+    pub ids: Vec<String>,
+}
+
+/// Response for POST /payments/status
+#[derive(Debug, Serialize)]
+pub struct BatchStatusResponse {
+    pub payments: Vec<PaymentStatusResponse>,
+    pub not_found: Vec<String>,
+}
+
+/// Query parameters for GET /payments
+#[derive(Debug, Default, Deserialize)]
+pub struct ListPaymentsQuery {
+    pub status: Option<String>,
+    pub token: Option<String>,
+    pub order_id: Option<String>,
+    pub customer_email: Option<String>,
+    pub sender_address: Option<String>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    pub paid_from: Option<DateTime<Utc>>,
+    pub paid_to: Option<DateTime<Utc>>,
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+    /// "created_at" (default) or "amount"
+    pub sort: Option<String>,
+    /// "desc" (default) or "asc"
+    pub order: Option<String>,
+    pub limit: Option<i64>,
+    /// Opaque cursor from a previous page's `next_cursor`
+    pub cursor: Option<String>,
+    /// Also return the total number of matching rows (extra COUNT query)
+    #[serde(default)]
+    pub include_total: bool,
+    /// Return a `PaymentListResponse` page instead of the legacy bare array
+    #[serde(default)]
+    pub paginate: bool,
+}
+
+impl ListPaymentsQuery {
+    /// Filter part of the query, for the payment repository
+    pub fn filter(&self) -> PaymentFilter {
+        PaymentFilter {
+            status: self.status.clone(),
+            token: self.token.clone(),
+            order_id: self.order_id.clone(),
+            customer_email: self.customer_email.clone(),
+            sender_address: self.sender_address.clone(),
+            created_from: self.created_from,
+            created_to: self.created_to,
+            paid_from: self.paid_from,
+            paid_to: self.paid_to,
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+        }
+    }
+}
+
+/// Paginated payment list response
+#[derive(Debug, Serialize)]
+pub struct PaymentListResponse {
+    pub payments: Vec<PaymentStatusResponse>,
+    pub next_cursor: Option<String>,
+    pub total_count: Option<i64>,
+}
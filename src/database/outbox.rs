@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::services::jobs::{JobEnvelope, QueueJob, WebhookDeliveryJob};
+use crate::services::webhooks::WebhookEvent;
+
+/// Job waiting in the outbox to be relayed to its queue (matches outbox table)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub queue: String,
+    #[sqlx(json)]
+    pub envelope: JobEnvelope,
+    /// Queued with a delayed enqueue when set
+    pub run_at: Option<DateTime<Utc>>,
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Record a transaction signature as processed
+/// Returns false if it was already recorded (another run got there first)
+pub async fn record_signature(
+    tx: &mut Transaction<'_, Postgres>,
+    signature: &str,
+    payment_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO processed_signatures (signature, payment_id)
+        VALUES ($1, $2)
+        ON CONFLICT (signature) DO NOTHING
+        "#,
+    )
+    .bind(signature)
+    .bind(payment_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Add a job to the outbox inside the caller's transaction
+pub async fn enqueue(
+    tx: &mut Transaction<'_, Postgres>,
+    queue: &str,
+    envelope: &JobEnvelope,
+) -> Result<(), sqlx::Error> {
+    insert_entry(tx, queue, envelope, None).await
+}
+
+/// Add a job that becomes available at `run_at` inside the caller's transaction
+pub async fn enqueue_at(
+    tx: &mut Transaction<'_, Postgres>,
+    queue: &str,
+    envelope: &JobEnvelope,
+    run_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    insert_entry(tx, queue, envelope, Some(run_at)).await
+}
+
+/// Add a typed job to its queue's outbox inside the caller's transaction
+pub async fn enqueue_job<J: QueueJob>(tx: &mut Transaction<'_, Postgres>, job: &J) -> Result<(), sqlx::Error> {
+    let envelope = JobEnvelope::new(job).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+    enqueue(tx, J::QUEUE, &envelope).await
+}
+
+/// Add one delivery job per active merchant endpoint subscribed to the event,
+/// inside the transaction that made the state change
+pub async fn enqueue_webhooks(
+    tx: &mut Transaction<'_, Postgres>,
+    merchant_address: &str,
+    event: &WebhookEvent,
+) -> Result<(), sqlx::Error> {
+    let endpoints: Vec<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT id FROM webhook_endpoints
+        WHERE merchant_address = $1 AND active = TRUE
+          AND (cardinality(event_types) = 0 OR $2 = ANY(event_types))
+        "#,
+    )
+    .bind(merchant_address)
+    .bind(&event.event_type)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for (endpoint_id,) in endpoints {
+        let job = WebhookDeliveryJob {
+            endpoint_id,
+            event: event.clone(),
+        };
+        enqueue_job(tx, &job).await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    queue: &str,
+    envelope: &JobEnvelope,
+    run_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO outbox (queue, job_id, envelope, run_at) VALUES ($1, $2, $3, $4)")
+        .bind(queue)
+        .bind(&envelope.id)
+        .bind(sqlx::types::Json(envelope))
+        .bind(run_at)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Record a matched payment's signature and its confirmation job together,
+/// so a crash can neither lose the job nor queue it twice
+/// Returns false (writing nothing) if the signature was already processed
+pub async fn record_matched_payment(
+    pool: &PgPool,
+    signature: &str,
+    payment_id: Uuid,
+    queue: &str,
+    envelope: &JobEnvelope,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if !record_signature(&mut tx, signature, Some(payment_id)).await? {
+        return Ok(false);
+    }
+    enqueue(&mut tx, queue, envelope).await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Whether a signature has already been handled
+pub async fn is_signature_processed(pool: &PgPool, signature: &str) -> Result<bool, sqlx::Error> {
+    let (exists,) = sqlx::query_as::<_, (bool,)>(
+        "SELECT EXISTS(SELECT 1 FROM processed_signatures WHERE signature = $1)",
+    )
+    .bind(signature)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// Lock the oldest unsent entries for relaying
+/// Rows locked by another relay are skipped, so several indexers can run one each
+pub async fn lock_unsent(
+    tx: &mut Transaction<'_, Postgres>,
+    limit: i64,
+) -> Result<Vec<OutboxEntry>, sqlx::Error> {
+    sqlx::query_as::<_, OutboxEntry>(
+        r#"
+        SELECT id, queue, envelope, run_at, attempts, created_at
+        FROM outbox
+        WHERE sent_at IS NULL
+        ORDER BY id
+        LIMIT $1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(&mut **tx)
+    .await
+}
+
+pub async fn mark_sent(tx: &mut Transaction<'_, Postgres>, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE outbox SET sent_at = NOW(), attempts = attempts + 1, last_error = NULL WHERE id = $1")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn mark_failed(tx: &mut Transaction<'_, Postgres>, id: i64, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1")
+        .bind(id)
+        .bind(error)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete relayed entries older than the given number of days
+pub async fn prune_sent(pool: &PgPool, older_than_days: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM outbox WHERE sent_at IS NOT NULL AND sent_at < NOW() - make_interval(days => $1)",
+    )
+    .bind(older_than_days as i32)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,557 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+use super::{NewPayment, PageRequest, PaymentFilter, PaymentRepository, SortOrder};
+use crate::database::confirmations::{conflict_reason, ConfirmOutcome, ConfirmationConflict};
+use crate::database::models::PaymentRequest;
+use crate::services::jobs::{EmailReceiptJob, JobEnvelope, PaymentConfirmationJob, PaymentExpiryJob};
+use crate::services::webhooks::{
+    payment_webhook_event, WebhookEvent, EVENT_PAYMENT_CONFIRMED, EVENT_PAYMENT_CREATED, EVENT_PAYMENT_EXPIRED,
+    EVENT_PAYMENT_UNDERPAID,
+};
+
+/// In-process payments for tests and development without Postgres
+/// Clones share the same payments; follows the Postgres rules for
+/// unique memos, pending-only confirmation and one payment per signature
+#[derive(Clone, Default)]
+pub struct MemoryPaymentRepository {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    payments: HashMap<Uuid, PaymentRequest>,
+    /// Events that Postgres would write to the `events` table, oldest first
+    events: Vec<WebhookEvent>,
+    /// Conflicts keyed by (payment id, signature), like the table's unique key
+    conflicts: HashMap<(Uuid, String), ConfirmationConflict>,
+    /// (merchant, order id) of payments created with `order_id_unique`
+    unique_orders: HashSet<(String, String)>,
+    /// Expiry jobs Postgres would put in the outbox, with their run time
+    scheduled: Vec<(JobEnvelope, DateTime<Utc>)>,
+    /// Receipt jobs Postgres would put in the outbox to run right away
+    /// (there are no webhook endpoints here, so no delivery jobs)
+    queued: Vec<JobEnvelope>,
+}
+
+impl MemoryPaymentRepository {
+    pub fn new() -> Self {
+        MemoryPaymentRepository::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Events recorded so far, oldest first
+    pub fn events(&self) -> Vec<WebhookEvent> {
+        self.lock().events.clone()
+    }
+
+    /// Expiry jobs scheduled so far, with their run time
+    pub fn scheduled_jobs(&self) -> Vec<(JobEnvelope, DateTime<Utc>)> {
+        self.lock().scheduled.clone()
+    }
+
+    /// Jobs queued to run right away so far
+    pub fn queued_jobs(&self) -> Vec<JobEnvelope> {
+        self.lock().queued.clone()
+    }
+
+    /// Refused confirmations recorded so far
+    pub fn conflicts(&self) -> Vec<ConfirmationConflict> {
+        self.lock().conflicts.values().cloned().collect()
+    }
+
+    fn find(&self, predicate: impl Fn(&PaymentRequest) -> bool) -> Option<PaymentRequest> {
+        self.lock().payments.values().find(|payment| predicate(payment)).cloned()
+    }
+}
+
+impl MemoryState {
+    /// Expire pending payments past their expiry - one payment, or all of them
+    fn expire_pending(&mut self, payment_id: Option<Uuid>) -> Vec<(PaymentRequest, WebhookEvent)> {
+        let now = Utc::now();
+        let mut expired = Vec::new();
+
+        for payment in self.payments.values_mut() {
+            let selected = payment_id.is_none_or(|id| id == payment.id);
+            if selected && payment.status == "pending" && payment.expires_at.is_some_and(|expires_at| expires_at <= now) {
+                payment.status = "expired".to_string();
+                payment.updated_at = now;
+                expired.push((payment.clone(), payment_webhook_event(EVENT_PAYMENT_EXPIRED, payment)));
+            }
+        }
+
+        self.events.extend(expired.iter().map(|(_, event)| event.clone()));
+
+        expired
+    }
+
+    /// Log a refused signature, once per payment and signature
+    fn record_conflict(
+        &mut self,
+        job: &PaymentConfirmationJob,
+        existing: Option<&PaymentRequest>,
+        reason: String,
+    ) -> ConfirmationConflict {
+        let conflict = self
+            .conflicts
+            .entry((job.payment_id, job.tx_sig.clone()))
+            .or_insert_with(|| ConfirmationConflict {
+                id: Uuid::new_v4(),
+                payment_id: job.payment_id,
+                memo: job.memo.clone(),
+                tx_sig: job.tx_sig.clone(),
+                sender_address: job.sender_address.clone(),
+                amount_lamports: job.amount_lamports,
+                existing_status: None,
+                existing_tx_sig: None,
+                reason: String::new(),
+                created_at: Utc::now(),
+            });
+
+        conflict.existing_status = existing.map(|payment| payment.status.clone());
+        conflict.existing_tx_sig = existing.and_then(|payment| payment.tx_sig.clone());
+        conflict.reason = reason;
+
+        conflict.clone()
+    }
+}
+
+#[async_trait]
+impl PaymentRepository for MemoryPaymentRepository {
+    async fn create(&self, new: NewPayment) -> Result<(PaymentRequest, WebhookEvent), sqlx::Error> {
+        let mut state = self.lock();
+
+        if state.payments.values().any(|payment| payment.memo == new.memo) {
+            return Err(sqlx::Error::Protocol(format!("Duplicate memo {}", new.memo)));
+        }
+        // Same rule as the partial idx_order_id_unique index
+        if let (true, Some(order_id)) = (new.order_id_unique, &new.order_id) {
+            let key = (new.receiver_address.clone(), order_id.clone());
+            if !state.unique_orders.insert(key) {
+                return Err(sqlx::Error::Protocol(format!("Duplicate order_id {}", order_id)));
+            }
+        }
+
+        let payment = PaymentRequest {
+            id: new.id,
+            amount_lamports: new.amount_lamports,
+            token_symbol: new.token_symbol,
+            memo: new.memo,
+            status: "pending".to_string(),
+            receiver_address: new.receiver_address,
+            sender_address: None,
+            tx_sig: None,
+            block_height: None,
+            created_at: new.created_at,
+            paid_at: None,
+            updated_at: new.created_at,
+            expires_at: Some(new.expires_at),
+            order_id: new.order_id,
+            customer_email: new.customer_email,
+            reference: Some(new.reference),
+        };
+
+        let event = payment_webhook_event(EVENT_PAYMENT_CREATED, &payment);
+        let expiry = PaymentExpiryJob::envelope(payment.id).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        state.payments.insert(payment.id, payment.clone());
+        state.events.push(event.clone());
+        state.scheduled.push((expiry, new.expires_at));
+
+        Ok((payment, event))
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        Ok(self.lock().payments.get(&id).cloned())
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<PaymentRequest>, sqlx::Error> {
+        let state = self.lock();
+
+        Ok(ids.iter().filter_map(|id| state.payments.get(id).cloned()).collect())
+    }
+
+    async fn find_by_order(&self, receiver_address: &str, order_id: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        let state = self.lock();
+
+        Ok(state
+            .payments
+            .values()
+            .filter(|payment| payment.receiver_address == receiver_address && payment.order_id.as_deref() == Some(order_id))
+            .max_by_key(|payment| payment.created_at)
+            .cloned())
+    }
+
+    async fn find_by_memo(&self, receiver_address: &str, memo: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        Ok(self.find(|payment| payment.receiver_address == receiver_address && payment.memo == memo))
+    }
+
+    async fn find_by_tx(&self, receiver_address: &str, tx_sig: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        Ok(self.find(|payment| payment.receiver_address == receiver_address && payment.tx_sig.as_deref() == Some(tx_sig)))
+    }
+
+    async fn list(&self, filter: &PaymentFilter, page: &PageRequest) -> Result<Vec<PaymentRequest>, sqlx::Error> {
+        let state = self.lock();
+
+        let mut payments: Vec<PaymentRequest> = state
+            .payments
+            .values()
+            .filter(|payment| filter.matches(payment))
+            .filter(|payment| match page.after {
+                None => true,
+                Some(after) => {
+                    let position = (page.sort.key(payment), payment.id);
+                    match page.order {
+                        SortOrder::Asc => position > after,
+                        SortOrder::Desc => position < after,
+                    }
+                }
+            })
+            .cloned()
+            .collect();
+
+        payments.sort_by_key(|payment| (page.sort.key(payment), payment.id));
+        if page.order == SortOrder::Desc {
+            payments.reverse();
+        }
+        payments.truncate(page.limit.max(0) as usize);
+
+        Ok(payments)
+    }
+
+    async fn count(&self, filter: &PaymentFilter) -> Result<i64, sqlx::Error> {
+        Ok(self.lock().payments.values().filter(|payment| filter.matches(payment)).count() as i64)
+    }
+
+    async fn confirm(&self, job: &PaymentConfirmationJob) -> Result<ConfirmOutcome, sqlx::Error> {
+        let mut state = self.lock();
+
+        let Some(payment) = state.payments.get(&job.payment_id).cloned() else {
+            return Ok(ConfirmOutcome::NotFound);
+        };
+
+        if payment.status != "pending" || payment.token_symbol != job.token_symbol {
+            if payment.tx_sig.as_deref() == Some(job.tx_sig.as_str()) {
+                return Ok(ConfirmOutcome::AlreadyConfirmed(payment));
+            }
+
+            let conflict = state.record_conflict(job, Some(&payment), conflict_reason(job, &payment));
+            return Ok(ConfirmOutcome::Conflict(conflict));
+        }
+
+        // Same guarantee as the unique tx_sig index
+        let settled_elsewhere = state
+            .payments
+            .values()
+            .any(|other| other.id != payment.id && other.tx_sig.as_deref() == Some(job.tx_sig.as_str()));
+        if settled_elsewhere {
+            let conflict = state.record_conflict(job, Some(&payment), "Signature already settled another payment".to_string());
+            return Ok(ConfirmOutcome::Conflict(conflict));
+        }
+
+        let underpaid = job.amount_lamports <= 0 || job.amount_lamports < payment.amount_lamports;
+        let mut payment = payment;
+        payment.status = if underpaid { "underpaid" } else { "confirmed" }.to_string();
+        payment.sender_address = Some(job.sender_address.clone());
+        payment.tx_sig = Some(job.tx_sig.clone());
+        payment.paid_at = job.paid_at;
+        payment.updated_at = Utc::now();
+
+        let event_type = if underpaid { EVENT_PAYMENT_UNDERPAID } else { EVENT_PAYMENT_CONFIRMED };
+        let event = payment_webhook_event(event_type, &payment);
+        if payment.customer_email.is_some() {
+            let receipt = JobEnvelope::new(&EmailReceiptJob { payment_id: payment.id })
+                .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+            state.queued.push(receipt);
+        }
+        state.payments.insert(payment.id, payment.clone());
+        state.events.push(event.clone());
+
+        Ok(ConfirmOutcome::Updated { payment, event })
+    }
+
+    async fn expire(&self, id: Uuid) -> Result<Option<(PaymentRequest, WebhookEvent)>, sqlx::Error> {
+        Ok(self.lock().expire_pending(Some(id)).pop())
+    }
+
+    async fn expire_overdue(&self) -> Result<Vec<(PaymentRequest, WebhookEvent)>, sqlx::Error> {
+        Ok(self.lock().expire_pending(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::SortField;
+    use super::*;
+    use chrono::Duration;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+
+    fn new_payment(amount_lamports: i64, expires_in: Duration) -> NewPayment {
+        let now = Utc::now();
+        NewPayment {
+            id: Uuid::new_v4(),
+            amount_lamports,
+            token_symbol: "SOL".to_string(),
+            memo: format!("memo-{}", Uuid::new_v4()),
+            receiver_address: "merchant".to_string(),
+            created_at: now,
+            expires_at: now + expires_in,
+            order_id: None,
+            order_id_unique: false,
+            customer_email: None,
+            reference: Pubkey::new_unique().to_string(),
+        }
+    }
+
+    fn confirmation(payment: &PaymentRequest, amount_lamports: i64) -> PaymentConfirmationJob {
+        PaymentConfirmationJob {
+            job_id: Uuid::new_v4().to_string(),
+            payment_id: payment.id,
+            memo: payment.memo.clone(),
+            sender_address: Pubkey::new_unique().to_string(),
+            tx_sig: Signature::new_unique().to_string(),
+            token_symbol: payment.token_symbol.clone(),
+            amount_lamports,
+            fee_payer: Pubkey::new_unique().to_string(),
+            fee_lamports: 5000,
+            paid_at: Some(Utc::now()),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_records_event_and_schedules_expiry() {
+        let repo = MemoryPaymentRepository::new();
+        let new = new_payment(1_000, Duration::minutes(15));
+        let expires_at = new.expires_at;
+
+        let (payment, event) = repo.create(new).await.unwrap();
+
+        assert_eq!(payment.status, "pending");
+        assert_eq!(event.event_type, EVENT_PAYMENT_CREATED);
+        assert_eq!(repo.get(payment.id).await.unwrap().unwrap().memo, payment.memo);
+
+        let scheduled = repo.scheduled_jobs();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].0.id, PaymentExpiryJob::job_id(payment.id));
+        assert_eq!(scheduled[0].1, expires_at);
+    }
+
+    #[tokio::test]
+    async fn create_rejects_duplicate_memo_and_unique_order() {
+        let repo = MemoryPaymentRepository::new();
+        let mut first = new_payment(1_000, Duration::minutes(15));
+        first.order_id = Some("order-1".to_string());
+        first.order_id_unique = true;
+        repo.create(first.clone()).await.unwrap();
+
+        let mut same_memo = new_payment(1_000, Duration::minutes(15));
+        same_memo.memo = first.memo.clone();
+        assert!(repo.create(same_memo).await.is_err());
+
+        let mut same_order = new_payment(1_000, Duration::minutes(15));
+        same_order.order_id = first.order_id.clone();
+        same_order.order_id_unique = true;
+        assert!(repo.create(same_order).await.is_err());
+
+        // Another merchant may reuse the order id
+        let mut other_merchant = new_payment(1_000, Duration::minutes(15));
+        other_merchant.order_id = first.order_id.clone();
+        other_merchant.order_id_unique = true;
+        other_merchant.receiver_address = "other".to_string();
+        assert!(repo.create(other_merchant).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn confirm_settles_pending_payment() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let job = confirmation(&payment, 1_000);
+
+        let ConfirmOutcome::Updated { payment, event } = repo.confirm(&job).await.unwrap() else {
+            panic!("expected Updated");
+        };
+        assert_eq!(payment.status, "confirmed");
+        assert_eq!(payment.tx_sig.as_deref(), Some(job.tx_sig.as_str()));
+        assert_eq!(payment.sender_address.as_deref(), Some(job.sender_address.as_str()));
+        assert_eq!(event.event_type, EVENT_PAYMENT_CONFIRMED);
+
+        let found = repo.find_by_tx("merchant", &job.tx_sig).await.unwrap();
+        assert_eq!(found.map(|found| found.id), Some(payment.id));
+        assert!(repo.find_by_tx("other", &job.tx_sig).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn confirm_queues_receipt_only_with_customer_email() {
+        let repo = MemoryPaymentRepository::new();
+        let (without_email, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let (with_email, _) = repo
+            .create(NewPayment {
+                customer_email: Some("buyer@example.com".to_string()),
+                ..new_payment(1_000, Duration::minutes(15))
+            })
+            .await
+            .unwrap();
+
+        repo.confirm(&confirmation(&without_email, 1_000)).await.unwrap();
+        repo.confirm(&confirmation(&with_email, 1_000)).await.unwrap();
+
+        let queued = repo.queued_jobs();
+        assert_eq!(queued.len(), 1);
+        let receipt: EmailReceiptJob = queued[0].decode().unwrap();
+        assert_eq!(receipt.payment_id, with_email.id);
+    }
+
+    #[tokio::test]
+    async fn confirm_marks_short_payment_underpaid() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+
+        let ConfirmOutcome::Updated { payment, event } = repo.confirm(&confirmation(&payment, 999)).await.unwrap() else {
+            panic!("expected Updated");
+        };
+        assert_eq!(payment.status, "underpaid");
+        assert_eq!(event.event_type, EVENT_PAYMENT_UNDERPAID);
+    }
+
+    #[tokio::test]
+    async fn confirm_redelivery_is_already_confirmed() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let job = confirmation(&payment, 1_000);
+        repo.confirm(&job).await.unwrap();
+
+        let outcome = repo.confirm(&job).await.unwrap();
+
+        assert!(matches!(outcome, ConfirmOutcome::AlreadyConfirmed(ref existing) if existing.id == payment.id));
+        assert_eq!(repo.events().len(), 2);
+        assert!(repo.conflicts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn confirm_with_second_signature_is_conflict() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let first = confirmation(&payment, 1_000);
+        repo.confirm(&first).await.unwrap();
+
+        let ConfirmOutcome::Conflict(conflict) = repo.confirm(&confirmation(&payment, 1_000)).await.unwrap() else {
+            panic!("expected Conflict");
+        };
+        assert_eq!(conflict.existing_status.as_deref(), Some("confirmed"));
+        assert_eq!(conflict.existing_tx_sig.as_deref(), Some(first.tx_sig.as_str()));
+        assert_eq!(conflict.reason, "Payment already confirmed by another signature");
+        assert_eq!(repo.conflicts().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn confirm_in_wrong_token_is_conflict() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let mut job = confirmation(&payment, 1_000);
+        job.token_symbol = "USDC".to_string();
+
+        let ConfirmOutcome::Conflict(conflict) = repo.confirm(&job).await.unwrap() else {
+            panic!("expected Conflict");
+        };
+        assert_eq!(conflict.reason, "Paid in USDC, payment expects SOL");
+        assert_eq!(repo.get(payment.id).await.unwrap().unwrap().status, "pending");
+    }
+
+    #[tokio::test]
+    async fn confirm_with_signature_settling_another_payment_is_conflict() {
+        let repo = MemoryPaymentRepository::new();
+        let (first, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let (second, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let job = confirmation(&first, 1_000);
+        repo.confirm(&job).await.unwrap();
+
+        let mut reused = confirmation(&second, 1_000);
+        reused.tx_sig = job.tx_sig.clone();
+
+        let ConfirmOutcome::Conflict(conflict) = repo.confirm(&reused).await.unwrap() else {
+            panic!("expected Conflict");
+        };
+        assert_eq!(conflict.reason, "Signature already settled another payment");
+        assert_eq!(repo.get(second.id).await.unwrap().unwrap().status, "pending");
+    }
+
+    #[tokio::test]
+    async fn confirm_expired_payment_is_conflict() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, -Duration::minutes(1))).await.unwrap();
+        repo.expire(payment.id).await.unwrap();
+
+        let ConfirmOutcome::Conflict(conflict) = repo.confirm(&confirmation(&payment, 1_000)).await.unwrap() else {
+            panic!("expected Conflict");
+        };
+        assert_eq!(conflict.existing_status.as_deref(), Some("expired"));
+        assert_eq!(conflict.reason, "Payment is expired");
+    }
+
+    #[tokio::test]
+    async fn confirm_unknown_payment_is_not_found() {
+        let repo = MemoryPaymentRepository::new();
+        let (payment, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+        let mut job = confirmation(&payment, 1_000);
+        job.payment_id = Uuid::new_v4();
+
+        assert!(matches!(repo.confirm(&job).await.unwrap(), ConfirmOutcome::NotFound));
+    }
+
+    #[tokio::test]
+    async fn expire_only_touches_overdue_pending_payments() {
+        let repo = MemoryPaymentRepository::new();
+        let (overdue, _) = repo.create(new_payment(1_000, -Duration::minutes(1))).await.unwrap();
+        let (current, _) = repo.create(new_payment(1_000, Duration::minutes(15))).await.unwrap();
+
+        assert!(repo.expire(current.id).await.unwrap().is_none());
+
+        let (expired, event) = repo.expire(overdue.id).await.unwrap().unwrap();
+        assert_eq!(expired.status, "expired");
+        assert_eq!(event.event_type, EVENT_PAYMENT_EXPIRED);
+
+        // Already expired - a second run is a no-op
+        assert!(repo.expire(overdue.id).await.unwrap().is_none());
+        assert!(repo.expire_overdue().await.unwrap().is_empty());
+        assert_eq!(repo.get(current.id).await.unwrap().unwrap().status, "pending");
+    }
+
+    #[tokio::test]
+    async fn expire_overdue_skips_confirmed_payments() {
+        let repo = MemoryPaymentRepository::new();
+        let (paid, _) = repo.create(new_payment(1_000, -Duration::minutes(1))).await.unwrap();
+        let (unpaid, _) = repo.create(new_payment(1_000, -Duration::minutes(1))).await.unwrap();
+        repo.confirm(&confirmation(&paid, 1_000)).await.unwrap();
+
+        let expired = repo.expire_overdue().await.unwrap();
+
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0.id, unpaid.id);
+        assert_eq!(repo.get(paid.id).await.unwrap().unwrap().status, "confirmed");
+    }
+
+    #[tokio::test]
+    async fn list_pages_by_keyset() {
+        let repo = MemoryPaymentRepository::new();
+        for amount in [300, 100, 200] {
+            repo.create(new_payment(amount, Duration::minutes(15))).await.unwrap();
+        }
+
+        let filter = PaymentFilter::default();
+        let mut page = PageRequest { sort: SortField::Amount, order: SortOrder::Asc, after: None, limit: 2 };
+        let first = repo.list(&filter, &page).await.unwrap();
+        assert_eq!(first.iter().map(|p| p.amount_lamports).collect::<Vec<_>>(), vec![100, 200]);
+
+        let last = first.last().unwrap();
+        page.after = Some((last.amount_lamports, last.id));
+        let second = repo.list(&filter, &page).await.unwrap();
+        assert_eq!(second.iter().map(|p| p.amount_lamports).collect::<Vec<_>>(), vec![300]);
+        assert_eq!(repo.count(&filter).await.unwrap(), 3);
+    }
+}
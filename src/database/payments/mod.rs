@@ -0,0 +1,266 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::confirmations::ConfirmOutcome;
+use super::models::PaymentRequest;
+use crate::services::jobs::PaymentConfirmationJob;
+use crate::services::webhooks::WebhookEvent;
+
+mod memory;
+mod postgres;
+
+pub use memory::MemoryPaymentRepository;
+pub use postgres::PgPaymentRepository;
+
+/// Payment persistence used by the API, indexer and worker
+/// Implemented on Postgres and in memory (for tests without a database)
+#[async_trait]
+pub trait PaymentRepository: Send + Sync {
+    /// Insert a pending payment, record its payment.created event and
+    /// schedule its `PaymentExpiryJob` for `expires_at`
+    async fn create(&self, payment: NewPayment) -> Result<(PaymentRequest, WebhookEvent), sqlx::Error>;
+
+    async fn get(&self, id: Uuid) -> Result<Option<PaymentRequest>, sqlx::Error>;
+
+    /// Payments with any of the ids, in no particular order (missing ids are skipped)
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<PaymentRequest>, sqlx::Error>;
+
+    /// Newest payment for one of a merchant's order ids
+    async fn find_by_order(&self, receiver_address: &str, order_id: &str) -> Result<Option<PaymentRequest>, sqlx::Error>;
+
+    /// A merchant's payment with this memo, whatever its status
+    async fn find_by_memo(&self, receiver_address: &str, memo: &str) -> Result<Option<PaymentRequest>, sqlx::Error>;
+
+    /// A merchant's payment settled by a transaction signature
+    async fn find_by_tx(&self, receiver_address: &str, tx_sig: &str) -> Result<Option<PaymentRequest>, sqlx::Error>;
+
+    /// One page of payments matching the filter, in the page's sort order
+    async fn list(&self, filter: &PaymentFilter, page: &PageRequest) -> Result<Vec<PaymentRequest>, sqlx::Error>;
+
+    /// Number of payments matching the filter
+    async fn count(&self, filter: &PaymentFilter) -> Result<i64, sqlx::Error>;
+
+    /// Settle a pending payment from a confirmation job, exactly once
+    async fn confirm(&self, job: &PaymentConfirmationJob) -> Result<ConfirmOutcome, sqlx::Error>;
+
+    /// Expire one payment if it is still pending and past its expiry, returning it with its event
+    async fn expire(&self, id: Uuid) -> Result<Option<(PaymentRequest, WebhookEvent)>, sqlx::Error>;
+
+    /// Mark pending payments past their expiry expired, returning them with their events
+    async fn expire_overdue(&self) -> Result<Vec<(PaymentRequest, WebhookEvent)>, sqlx::Error>;
+}
+
+/// Fields of a payment request about to be created
+#[derive(Debug, Clone)]
+pub struct NewPayment {
+    pub id: Uuid,
+    pub amount_lamports: i64,
+    pub token_symbol: String,
+    pub memo: String,
+    pub receiver_address: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub order_id: Option<String>,
+    /// No other payment created with this flag may share the order_id (ORDER_ID_UNIQUE)
+    pub order_id_unique: bool,
+    pub customer_email: Option<String>,
+    pub reference: String,
+}
+
+/// Filters for listing payments - unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct PaymentFilter {
+    pub status: Option<String>,
+    /// Case-insensitive token symbol
+    pub token: Option<String>,
+    pub order_id: Option<String>,
+    /// Case-insensitive customer email
+    pub customer_email: Option<String>,
+    pub sender_address: Option<String>,
+    /// created_at range, inclusive start and exclusive end
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    /// paid_at range, inclusive start and exclusive end
+    pub paid_from: Option<DateTime<Utc>>,
+    pub paid_to: Option<DateTime<Utc>>,
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+}
+
+impl PaymentFilter {
+    /// Whether a payment passes every filter (the in-memory WHERE clause)
+    pub fn matches(&self, payment: &PaymentRequest) -> bool {
+        let within = |value: Option<DateTime<Utc>>, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>| {
+            (from.is_none() && to.is_none())
+                || value.is_some_and(|value| from.is_none_or(|from| value >= from) && to.is_none_or(|to| value < to))
+        };
+
+        self.status.as_ref().is_none_or(|status| payment.status == *status)
+            && self.token.as_ref().is_none_or(|token| payment.token_symbol.to_uppercase() == token.to_uppercase())
+            && self.order_id.as_ref().is_none_or(|order_id| payment.order_id.as_ref() == Some(order_id))
+            && self.customer_email.as_ref().is_none_or(|email| {
+                payment.customer_email.as_ref().is_some_and(|customer| customer.to_lowercase() == email.to_lowercase())
+            })
+            && self.sender_address.as_ref().is_none_or(|sender| payment.sender_address.as_ref() == Some(sender))
+            && within(Some(payment.created_at), self.created_from, self.created_to)
+            && within(payment.paid_at, self.paid_from, self.paid_to)
+            && self.min_amount.is_none_or(|min| payment.amount_lamports >= min)
+            && self.max_amount.is_none_or(|max| payment.amount_lamports <= max)
+    }
+}
+
+/// Columns payments can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    CreatedAt,
+    Amount,
+}
+
+impl SortField {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created_at" => Some(SortField::CreatedAt),
+            "amount" | "amount_lamports" => Some(SortField::Amount),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::Amount => "amount",
+        }
+    }
+
+    /// Database column backing this sort
+    pub fn column(&self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::Amount => "amount_lamports",
+        }
+    }
+
+    /// Sort key as stored in page cursors (created_at in microseconds or amount)
+    pub fn key(&self, payment: &PaymentRequest) -> i64 {
+        match self {
+            SortField::CreatedAt => payment.created_at.timestamp_micros(),
+            SortField::Amount => payment.amount_lamports,
+        }
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "asc" => Some(SortOrder::Asc),
+            "desc" => Some(SortOrder::Desc),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+
+    pub fn sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Sort and keyset position for one page of payments
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub sort: SortField,
+    pub order: SortOrder,
+    /// Sort key and id of the last row on the previous page
+    pub after: Option<(i64, Uuid)>,
+    pub limit: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn payment() -> PaymentRequest {
+        let created_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        PaymentRequest {
+            id: Uuid::new_v4(),
+            amount_lamports: 1_000,
+            token_symbol: "USDC".to_string(),
+            memo: "memo".to_string(),
+            status: "confirmed".to_string(),
+            receiver_address: "merchant".to_string(),
+            sender_address: Some("sender".to_string()),
+            tx_sig: Some("sig".to_string()),
+            block_height: None,
+            created_at,
+            paid_at: Some(created_at + Duration::minutes(5)),
+            updated_at: created_at,
+            expires_at: None,
+            order_id: Some("order-1".to_string()),
+            customer_email: Some("Buyer@Example.com".to_string()),
+            reference: None,
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert!(PaymentFilter::default().matches(&payment()));
+    }
+
+    #[test]
+    fn matches_exact_and_case_insensitive_fields() {
+        let payment = payment();
+        let filter = |f: PaymentFilter| f.matches(&payment);
+
+        assert!(filter(PaymentFilter { status: Some("confirmed".to_string()), ..Default::default() }));
+        assert!(!filter(PaymentFilter { status: Some("pending".to_string()), ..Default::default() }));
+        assert!(filter(PaymentFilter { token: Some("usdc".to_string()), ..Default::default() }));
+        assert!(filter(PaymentFilter { customer_email: Some("buyer@example.COM".to_string()), ..Default::default() }));
+        assert!(filter(PaymentFilter { order_id: Some("order-1".to_string()), ..Default::default() }));
+        assert!(!filter(PaymentFilter { order_id: Some("ORDER-1".to_string()), ..Default::default() }));
+        assert!(!filter(PaymentFilter { sender_address: Some("someone".to_string()), ..Default::default() }));
+    }
+
+    #[test]
+    fn date_ranges_include_start_and_exclude_end() {
+        let payment = payment();
+        let created = payment.created_at;
+
+        let range = |from, to| PaymentFilter { created_from: from, created_to: to, ..Default::default() };
+        assert!(range(Some(created), None).matches(&payment));
+        assert!(!range(None, Some(created)).matches(&payment));
+        assert!(range(None, Some(created + Duration::seconds(1))).matches(&payment));
+
+        // A paid_at range never matches an unpaid payment
+        let mut unpaid = payment.clone();
+        unpaid.paid_at = None;
+        let paid_range = PaymentFilter { paid_from: Some(created), ..Default::default() };
+        assert!(paid_range.matches(&payment));
+        assert!(!paid_range.matches(&unpaid));
+    }
+
+    #[test]
+    fn amount_bounds_are_inclusive() {
+        let payment = payment();
+
+        assert!(PaymentFilter { min_amount: Some(1_000), max_amount: Some(1_000), ..Default::default() }.matches(&payment));
+        assert!(!PaymentFilter { min_amount: Some(1_001), ..Default::default() }.matches(&payment));
+        assert!(!PaymentFilter { max_amount: Some(999), ..Default::default() }.matches(&payment));
+    }
+}
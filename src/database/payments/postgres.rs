@@ -0,0 +1,240 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{NewPayment, PageRequest, PaymentFilter, PaymentRepository, SortField, SortOrder};
+use crate::database::confirmations::{confirm_payment, ConfirmOutcome};
+use crate::database::events::record_event;
+use crate::database::fees::release_sponsored_fees;
+use crate::database::outbox;
+use crate::database::models::PaymentRequest;
+use crate::services::jobs::{PaymentConfirmationJob, PaymentExpiryJob, QueueJob};
+use crate::services::webhooks::{
+    payment_webhook_event, WebhookEvent, EVENT_PAYMENT_CREATED, EVENT_PAYMENT_EXPIRED,
+};
+
+/// Payments in the `payment_requests` table
+/// State changes write their event to the `events` table in the same transaction
+#[derive(Clone)]
+pub struct PgPaymentRepository {
+    pool: PgPool,
+}
+
+impl PgPaymentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PgPaymentRepository { pool }
+    }
+
+    /// Expire pending payments past their expiry - one payment, or all of them
+    async fn expire_pending(&self, payment_id: Option<Uuid>) -> Result<Vec<(PaymentRequest, WebhookEvent)>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let payments = sqlx::query_as::<_, PaymentRequest>(
+            r#"
+            UPDATE payment_requests
+            SET status = 'expired', updated_at = NOW()
+            WHERE status = 'pending' AND expires_at <= NOW() AND ($1::UUID IS NULL OR id = $1)
+            RETURNING *
+            "#,
+        )
+        .bind(payment_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let ids: Vec<Uuid> = payments.iter().map(|payment| payment.id).collect();
+        release_sponsored_fees(&mut tx, &ids).await?;
+
+        let mut expired = Vec::with_capacity(payments.len());
+        for payment in payments {
+            let event = payment_webhook_event(EVENT_PAYMENT_EXPIRED, &payment);
+            record_event(&mut tx, &payment.receiver_address, Some(payment.id), &event).await?;
+            expired.push((payment, event));
+        }
+
+        tx.commit().await?;
+
+        Ok(expired)
+    }
+
+    /// One of a merchant's payments by a unique column
+    async fn find_where(&self, receiver_address: &str, column: &str, value: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        sqlx::query_as::<_, PaymentRequest>(&format!(
+            "SELECT * FROM payment_requests WHERE receiver_address = $1 AND {} = $2",
+            column
+        ))
+        .bind(receiver_address)
+        .bind(value)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[async_trait]
+impl PaymentRepository for PgPaymentRepository {
+    async fn create(&self, payment: NewPayment) -> Result<(PaymentRequest, WebhookEvent), sqlx::Error> {
+        let expires_at = payment.expires_at;
+        let mut tx = self.pool.begin().await?;
+
+        let payment = sqlx::query_as::<_, PaymentRequest>(
+            r#"
+            INSERT INTO payment_requests
+            (id, amount_lamports, token_symbol, memo, status, receiver_address, created_at, expires_at, order_id, order_id_unique, customer_email, reference)
+            VALUES ($1, $2, $3, $4, 'pending', $5, $6, $7, $8, $9, $10, $11)
+            RETURNING *
+            "#,
+        )
+        .bind(payment.id)
+        .bind(payment.amount_lamports)
+        .bind(&payment.token_symbol)
+        .bind(&payment.memo)
+        .bind(&payment.receiver_address)
+        .bind(payment.created_at)
+        .bind(payment.expires_at)
+        .bind(&payment.order_id)
+        .bind(payment.order_id_unique)
+        .bind(&payment.customer_email)
+        .bind(&payment.reference)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let event = payment_webhook_event(EVENT_PAYMENT_CREATED, &payment);
+        record_event(&mut tx, &payment.receiver_address, Some(payment.id), &event).await?;
+
+        // Relayed to the queue as a delayed job that runs at expires_at
+        let expiry = PaymentExpiryJob::envelope(payment.id).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        outbox::enqueue_at(&mut tx, PaymentExpiryJob::QUEUE, &expiry, expires_at).await?;
+
+        tx.commit().await?;
+
+        Ok((payment, event))
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        sqlx::query_as::<_, PaymentRequest>("SELECT * FROM payment_requests WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn get_many(&self, ids: &[Uuid]) -> Result<Vec<PaymentRequest>, sqlx::Error> {
+        sqlx::query_as::<_, PaymentRequest>("SELECT * FROM payment_requests WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_by_order(&self, receiver_address: &str, order_id: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        sqlx::query_as::<_, PaymentRequest>(
+            r#"
+            SELECT * FROM payment_requests
+            WHERE receiver_address = $1 AND order_id = $2
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(receiver_address)
+        .bind(order_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn find_by_memo(&self, receiver_address: &str, memo: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        self.find_where(receiver_address, "memo", memo).await
+    }
+
+    async fn find_by_tx(&self, receiver_address: &str, tx_sig: &str) -> Result<Option<PaymentRequest>, sqlx::Error> {
+        self.find_where(receiver_address, "tx_sig", tx_sig).await
+    }
+
+    async fn list(&self, filter: &PaymentFilter, page: &PageRequest) -> Result<Vec<PaymentRequest>, sqlx::Error> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM payment_requests WHERE 1=1");
+        push_payment_filters(&mut builder, filter);
+
+        // Keyset: rows strictly after the previous page's last (sort key, id)
+        if let Some((value, id)) = page.after {
+            let comparison = match page.order {
+                SortOrder::Asc => ">",
+                SortOrder::Desc => "<",
+            };
+            builder.push(format_args!(" AND ({}, id) {} (", page.sort.column(), comparison));
+            match page.sort {
+                SortField::CreatedAt => {
+                    let created_at = DateTime::<Utc>::from_timestamp_micros(value)
+                        .ok_or_else(|| sqlx::Error::Decode("Cursor timestamp out of range".into()))?;
+                    builder.push_bind(created_at);
+                }
+                SortField::Amount => {
+                    builder.push_bind(value);
+                }
+            }
+            builder.push(", ").push_bind(id).push(")");
+        }
+
+        builder.push(format_args!(
+            " ORDER BY {} {}, id {} LIMIT ",
+            page.sort.column(),
+            page.order.sql(),
+            page.order.sql()
+        ));
+        builder.push_bind(page.limit);
+
+        builder.build_query_as::<PaymentRequest>().fetch_all(&self.pool).await
+    }
+
+    async fn count(&self, filter: &PaymentFilter) -> Result<i64, sqlx::Error> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM payment_requests WHERE 1=1");
+        push_payment_filters(&mut builder, filter);
+
+        builder.build_query_scalar::<i64>().fetch_one(&self.pool).await
+    }
+
+    async fn confirm(&self, job: &PaymentConfirmationJob) -> Result<ConfirmOutcome, sqlx::Error> {
+        confirm_payment(&self.pool, job).await
+    }
+
+    async fn expire(&self, id: Uuid) -> Result<Option<(PaymentRequest, WebhookEvent)>, sqlx::Error> {
+        Ok(self.expire_pending(Some(id)).await?.pop())
+    }
+
+    async fn expire_overdue(&self) -> Result<Vec<(PaymentRequest, WebhookEvent)>, sqlx::Error> {
+        self.expire_pending(None).await
+    }
+}
+
+/// Append WHERE clauses for every filter that is set
+fn push_payment_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, filter: &'a PaymentFilter) {
+    if let Some(ref status) = filter.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(ref token) = filter.token {
+        builder.push(" AND UPPER(token_symbol) = UPPER(").push_bind(token).push(")");
+    }
+    if let Some(ref order_id) = filter.order_id {
+        builder.push(" AND order_id = ").push_bind(order_id);
+    }
+    if let Some(ref email) = filter.customer_email {
+        builder.push(" AND LOWER(customer_email) = LOWER(").push_bind(email).push(")");
+    }
+    if let Some(ref sender) = filter.sender_address {
+        builder.push(" AND sender_address = ").push_bind(sender);
+    }
+    if let Some(from) = filter.created_from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.created_to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+    if let Some(from) = filter.paid_from {
+        builder.push(" AND paid_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.paid_to {
+        builder.push(" AND paid_at < ").push_bind(to);
+    }
+    if let Some(min) = filter.min_amount {
+        builder.push(" AND amount_lamports >= ").push_bind(min);
+    }
+    if let Some(max) = filter.max_amount {
+        builder.push(" AND amount_lamports <= ").push_bind(max);
+    }
+}
@@ -0,0 +1,22 @@
+use sqlx::PgPool;
+
+/// Claim this interval's run of a periodic job
+/// Returns true for exactly one caller per interval across all worker replicas;
+/// a run counts as due a little early so timer drift doesn't skip intervals
+pub async fn claim_scheduled_run(pool: &PgPool, job_type: &str, interval_secs: u64) -> Result<bool, sqlx::Error> {
+    let claimed = sqlx::query(
+        r#"
+        INSERT INTO scheduler_runs (job_type, last_run_at)
+        VALUES ($1, NOW())
+        ON CONFLICT (job_type) DO UPDATE
+        SET last_run_at = NOW()
+        WHERE scheduler_runs.last_run_at <= NOW() - make_interval(secs => $2)
+        "#,
+    )
+    .bind(job_type)
+    .bind(interval_secs as f64 * 0.9)
+    .execute(pool)
+    .await?;
+
+    Ok(claimed.rows_affected() > 0)
+}
@@ -2,4 +2,4 @@ pub mod solana;
 pub mod parser;
 
 pub use solana::SolanaIndexer;
-pub use parser::{parse_transaction, payment_to_confirmation_job, ParsedPayment};
+pub use parser::{failed_transaction_fee, parse_transaction, payment_to_confirmation_job, ParsedPayment};
@@ -1,29 +1,35 @@
-use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use solana_system_interface::{instruction::SystemInstruction, program as system_program};
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
 use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::config::tokens::{find_token, find_token_by_mint, TokenInfo};
+pub use crate::services::jobs::PaymentConfirmationJob;
 
 /// Parsed payment data from blockchain transaction
 #[derive(Debug, Clone)]
 pub struct ParsedPayment {
     pub signature: String,
+    /// Token the wallet received
+    pub token_symbol: String,
+    /// Amount received, in the token's base units (lamports for SOL)
     pub amount_lamports: i64,
     pub sender_address: String,
     pub receiver_address: String,
+    /// Account that paid the network fee, and the fee it paid
+    pub fee_payer: String,
+    pub fee_lamports: i64,
     pub memo: Option<String>,
     pub block_time: Option<DateTime<Utc>>,
 }
 
-/// Payment confirmation job for queue
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct PaymentConfirmationJob {
-    pub job_id: String,
-    pub memo: String,
-    pub sender_address: String,
-    pub tx_sig: String,
-    pub amount_lamports: i64,
-    pub paid_at: Option<DateTime<Utc>>,
-}
-
 /// Parse Solana transaction to extract payment info
+/// A supported SPL token credited to an account the wallet owns takes
+/// precedence over the wallet's SOL balance change
 pub fn parse_transaction(
     tx: &EncodedConfirmedTransactionWithStatusMeta,
     signature: &str,
@@ -38,40 +44,18 @@ pub fn parse_transaction(
         return None;
     }
 
-    // Extract pre and post balances
-    let pre_balances = &meta.pre_balances;
-    let post_balances = &meta.post_balances;
-
-    // Get account keys and wallet index - handle both Parsed and Raw message types
+    // Get account keys - handle both Parsed and Raw message types
     let tx_data = &tx.transaction.transaction;
     
-    let (sender, wallet_index) = match tx_data {
+    let account_keys: Vec<String> = match tx_data {
         solana_transaction_status::EncodedTransaction::Json(ui_tx) => {
             match &ui_tx.message {
                 // Handle Parsed message type
                 solana_transaction_status::UiMessage::Parsed(parsed_msg) => {
-                    let keys = &parsed_msg.account_keys;
-                    
-                    // Find our wallet's index
-                    let idx = keys.iter().position(|acc| acc.pubkey == wallet_address)?;
-                    
-                    // Get sender (first account)
-                    let sender_pubkey = keys.first()?.pubkey.clone();
-                    
-                    (sender_pubkey, idx)
+                    parsed_msg.account_keys.iter().map(|acc| acc.pubkey.clone()).collect()
                 }
                 // Handle Raw message type
-                solana_transaction_status::UiMessage::Raw(raw_msg) => {
-                    let keys = &raw_msg.account_keys;
-                    
-                    // Find our wallet's index
-                    let idx = keys.iter().position(|key| key == wallet_address)?;
-                    
-                    // Get sender (first account)
-                    let sender_pubkey = keys.first()?.clone();
-                    
-                    (sender_pubkey, idx)
-                }
+                solana_transaction_status::UiMessage::Raw(raw_msg) => raw_msg.account_keys.clone(),
             }
         }
         _ => {
@@ -80,11 +64,34 @@ pub fn parse_transaction(
         }
     };
 
-    // Calculate amount received (post - pre balance)
-    let pre_balance = *pre_balances.get(wallet_index)? as i64;
-    let post_balance = *post_balances.get(wallet_index)? as i64;
-    
-    let amount_lamports = post_balance.saturating_sub(pre_balance);
+    // First account is the fee payer - the customer unless the fee was sponsored
+    let fee_payer = account_keys.first()?.clone();
+    let mut sender = fee_payer.clone();
+
+    let (token, amount_lamports) = match received_token(meta, wallet_address) {
+        Some((token, amount, token_sender)) => {
+            // The fee payer may be a sponsor - the token account owner paid
+            if let Some(token_sender) = token_sender {
+                sender = token_sender;
+            }
+            (token, amount)
+        }
+        None => {
+            // Only an account the wallet signs for can receive SOL
+            let wallet_index = account_keys.iter().position(|key| key == wallet_address)?;
+
+            // Calculate amount received (post - pre balance)
+            let pre_balance = *meta.pre_balances.get(wallet_index)? as i64;
+            let post_balance = *meta.post_balances.get(wallet_index)? as i64;
+
+            // The fee payer may be a sponsor - the transfer's source paid
+            if let Some(source) = sol_transfer_source(tx_data, &account_keys, wallet_address) {
+                sender = source;
+            }
+
+            (find_token("SOL")?, post_balance.saturating_sub(pre_balance))
+        }
+    };
 
     // Skip if no funds received
     if amount_lamports <= 0 {
@@ -96,22 +103,27 @@ pub fn parse_transaction(
 
     // Extract memo from log messages
     let logs_option: Option<Vec<String>> = match &meta.log_messages {
-        solana_transaction_status::option_serializer::OptionSerializer::Some(logs) => Some(logs.clone()),
-        solana_transaction_status::option_serializer::OptionSerializer::None => None,
-        solana_transaction_status::option_serializer::OptionSerializer::Skip => None,
+        OptionSerializer::Some(logs) => Some(logs.clone()),
+        OptionSerializer::None => None,
+        OptionSerializer::Skip => None,
     };
     
     let memo = extract_memo_from_logs(&logs_option);
 
     // Get block time
     let block_time = tx.block_time.map(|t| {
-        DateTime::from_timestamp(t, 0).unwrap_or_else(|| Utc::now())
+        DateTime::from_timestamp(t, 0).unwrap_or_else(Utc::now)
     });
 
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("📥 NEW PAYMENT DETECTED");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("💰 Amount: {} lamports ({} SOL)", amount_lamports, amount_lamports as f64 / 1_000_000_000.0);
+    println!(
+        "💰 Amount: {} base units ({} {})",
+        amount_lamports,
+        amount_lamports as f64 / 10f64.powi(token.decimals as i32),
+        token.symbol
+    );
     println!("👤 From: {}", sender);
     println!("📍 To: {}", receiver);
     if let Some(ref m) = memo {
@@ -127,14 +139,124 @@ pub fn parse_transaction(
 
     Some(ParsedPayment {
         signature: signature.to_string(),
+        token_symbol: token.symbol.to_string(),
         amount_lamports,
         sender_address: sender,
         receiver_address: receiver,
+        fee_payer,
+        fee_lamports: meta.fee as i64,
         memo,
         block_time,
     })
 }
 
+/// Supported SPL token credited to a token account owned by the wallet
+/// Returns the token, the base units received and the owner of the account it came from
+fn received_token(
+    meta: &UiTransactionStatusMeta,
+    wallet_address: &str,
+) -> Option<(&'static TokenInfo, i64, Option<String>)> {
+    let pre_balances = token_balances(&meta.pre_token_balances);
+    let post_balances = token_balances(&meta.post_token_balances);
+
+    let pre_amount = |account_index: u8| {
+        pre_balances
+            .iter()
+            .find(|balance| balance.account_index == account_index)
+            .and_then(|balance| balance.ui_token_amount.amount.parse::<i64>().ok())
+            // No pre balance - the account was created in this transaction
+            .unwrap_or(0)
+    };
+
+    for balance in post_balances {
+        if token_owner(balance) != Some(wallet_address) {
+            continue;
+        }
+        let Some(token) = find_token_by_mint(&balance.mint) else {
+            continue;
+        };
+        let Ok(post_amount) = balance.ui_token_amount.amount.parse::<i64>() else {
+            continue;
+        };
+
+        let received = post_amount.saturating_sub(pre_amount(balance.account_index));
+        if received <= 0 {
+            continue;
+        }
+
+        // The source is the same mint's account whose balance went down
+        let sender = post_balances
+            .iter()
+            .filter(|other| other.mint == balance.mint && other.account_index != balance.account_index)
+            .find(|other| {
+                other
+                    .ui_token_amount
+                    .amount
+                    .parse::<i64>()
+                    .is_ok_and(|amount| amount < pre_amount(other.account_index))
+            })
+            .and_then(|other| token_owner(other).map(str::to_string));
+
+        return Some((token, received, sender));
+    }
+
+    None
+}
+
+fn token_balances(balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>) -> &[UiTransactionTokenBalance] {
+    match balances {
+        OptionSerializer::Some(balances) => balances.as_slice(),
+        OptionSerializer::None | OptionSerializer::Skip => &[],
+    }
+}
+
+fn token_owner(balance: &UiTransactionTokenBalance) -> Option<&str> {
+    match &balance.owner {
+        OptionSerializer::Some(owner) => Some(owner.as_str()),
+        OptionSerializer::None | OptionSerializer::Skip => None,
+    }
+}
+
+/// Source account of the top-level system transfer to the wallet
+fn sol_transfer_source(tx_data: &EncodedTransaction, account_keys: &[String], wallet_address: &str) -> Option<String> {
+    let EncodedTransaction::Json(ui_tx) = tx_data else {
+        return None;
+    };
+    let system_program = system_program::id().to_string();
+
+    match &ui_tx.message {
+        UiMessage::Raw(raw_msg) => raw_msg.instructions.iter().find_map(|ix| {
+            if account_keys.get(ix.program_id_index as usize)? != &system_program {
+                return None;
+            }
+            let data = bs58::decode(&ix.data).into_vec().ok()?;
+            let SystemInstruction::Transfer { .. } = bincode::deserialize(&data).ok()? else {
+                return None;
+            };
+            let source = account_keys.get(*ix.accounts.first()? as usize)?;
+            let destination = account_keys.get(*ix.accounts.get(1)? as usize)?;
+            (destination == wallet_address).then(|| source.clone())
+        }),
+        UiMessage::Parsed(parsed_msg) => parsed_msg.instructions.iter().find_map(|ix| {
+            let UiInstruction::Parsed(UiParsedInstruction::Parsed(ix)) = ix else {
+                return None;
+            };
+            if ix.program_id != system_program || ix.parsed["type"] != "transfer" {
+                return None;
+            }
+            let info = &ix.parsed["info"];
+            (info["destination"] == wallet_address).then(|| info["source"].as_str().map(str::to_string))?
+        }),
+    }
+}
+
+/// Fee charged for a transaction that failed on-chain
+/// Failed transactions carry no payment but still cost the fee payer
+pub fn failed_transaction_fee(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<i64> {
+    let meta = tx.transaction.meta.as_ref()?;
+    meta.err.as_ref().map(|_| meta.fee as i64)
+}
+
 /// Extract memo from transaction log messages
 fn extract_memo_from_logs(logs: &Option<Vec<String>>) -> Option<String> {
     let logs = logs.as_ref()?;
@@ -143,13 +265,12 @@ fn extract_memo_from_logs(logs: &Option<Vec<String>>) -> Option<String> {
         // Memos appear in logs like: "Program log: Memo (len 13): "PAY-ABC123""
         if log.contains("Program log: Memo") {
             // Extract the memo content between quotes
-            if let Some(start) = log.find('"') {
-                if let Some(end) = log.rfind('"') {
-                    if start < end {
-                        let memo = &log[start + 1..end];
-                        return Some(memo.to_string());
-                    }
-                }
+            if let Some(start) = log.find('"')
+                && let Some(end) = log.rfind('"')
+                && start < end
+            {
+                let memo = &log[start + 1..end];
+                return Some(memo.to_string());
             }
         }
     }
@@ -157,17 +278,21 @@ fn extract_memo_from_logs(logs: &Option<Vec<String>>) -> Option<String> {
     None
 }
 
-/// Convert ParsedPayment to PaymentConfirmationJob
-pub fn payment_to_confirmation_job(payment: ParsedPayment) -> Option<PaymentConfirmationJob> {
+/// Convert ParsedPayment to PaymentConfirmationJob for the matched payment request
+pub fn payment_to_confirmation_job(payment: ParsedPayment, payment_id: Uuid) -> Option<PaymentConfirmationJob> {
     // Only create job if memo exists
     let memo = payment.memo?;
 
     Some(PaymentConfirmationJob {
         job_id: payment.signature.clone(),
+        payment_id,
         memo,
         sender_address: payment.sender_address,
         tx_sig: payment.signature,
+        token_symbol: payment.token_symbol,
         amount_lamports: payment.amount_lamports,
+        fee_payer: payment.fee_payer,
+        fee_lamports: payment.fee_lamports,
         paid_at: payment.block_time,
     })
 }
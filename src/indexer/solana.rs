@@ -14,11 +14,17 @@ use solana_transaction_status::{
 use std::str::FromStr;
 use std::time::Duration;
 
+use crate::config::tokens::SUPPORTED_TOKENS;
+use crate::services::transaction_builder::associated_token_address;
+
 /// Solana RPC Client wrapper
 /// Concept: Connects to Solana blockchain and fetches transaction data
 pub struct SolanaIndexer {
     client: RpcClient,
     wallet_address: Pubkey,
+    /// The wallet's token accounts for supported SPL tokens - SPL transfers
+    /// only touch these, not the wallet itself
+    token_accounts: Vec<Pubkey>,
 }
 
 impl SolanaIndexer {
@@ -33,28 +39,44 @@ impl SolanaIndexer {
 
         let wallet_pubkey = Pubkey::from_str(wallet_address)?;
 
+        let mut token_accounts = Vec::new();
+        for token in SUPPORTED_TOKENS {
+            if let Some(mint) = token.mint {
+                token_accounts.push(associated_token_address(&wallet_pubkey, &Pubkey::from_str(mint)?));
+            }
+        }
+
         println!("✅ Solana indexer connected to: {}", rpc_url);
         println!("👀 Watching wallet: {}", wallet_address);
+        for account in &token_accounts {
+            println!("👀 Watching token account: {}", account);
+        }
 
         Ok(SolanaIndexer {
             client,
             wallet_address: wallet_pubkey,
+            token_accounts,
         })
     }
 
     /// Get recent transaction signatures for our wallet
-    /// Concept: Fetch list of transactions involving our wallet
+    /// Concept: Fetch list of transactions involving our wallet or its token accounts
+    /// Returns up to `limit` signatures per watched address, without duplicates
     pub fn get_recent_signatures(
         &self,
         limit: usize,
     ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let signatures = self.client.get_signatures_for_address(&self.wallet_address)?;
+        let mut sig_strings: Vec<String> = Vec::new();
+
+        for address in std::iter::once(&self.wallet_address).chain(&self.token_accounts) {
+            let signatures = self.client.get_signatures_for_address(address)?;
 
-        let sig_strings: Vec<String> = signatures
-            .iter()
-            .take(limit)
-            .map(|sig| sig.signature.clone())
-            .collect();
+            for sig in signatures.iter().take(limit) {
+                if !sig_strings.contains(&sig.signature) {
+                    sig_strings.push(sig.signature.clone());
+                }
+            }
+        }
 
         Ok(sig_strings)
     }
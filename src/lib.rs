@@ -4,9 +4,10 @@ pub mod database;
 pub mod services;
 pub mod utils;
 pub mod indexer;
+pub mod worker;
 
 
 // Re-export commonly used types
 pub use config::Config;
 pub use database::Database;
-pub use services::queue::{PaymentJob, QueueService};
+pub use services::queue::{JobQueue, QueueService};
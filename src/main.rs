@@ -1,11 +1,18 @@
 mod api;
-mod config;
-mod database;
-mod services;
+
+use payment_gateway_rust::{config, database, services, utils};
 
 use tokio::net::TcpListener;
 use axum::Router;  
 use tower_http::services::ServeDir;
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+use std::future::IntoFuture;
+use std::time::Duration;
+use database::payments::PgPaymentRepository;
+use services::fee_sponsor::{FeeSponsor, SponsorMode};
+use services::transaction_builder::RpcBlockhashSource;
+use services::webhooks::WebhookDispatcher;
 
 #[tokio::main]
 async fn main() {
@@ -29,9 +36,11 @@ async fn main() {
         }
     };
 
-    // Initialize database (create tables)
-    if let Err(e) = db.init().await {
-        eprintln!("❌ Failed to initialize database: {}", e);
+    // Bring the schema up to date (or leave it to `cargo run --bin migrate`)
+    if config.migrate_on_startup
+        && let Err(e) = db.migrate().await
+    {
+        eprintln!("❌ Failed to migrate database: {}", e);
         std::process::exit(1);
     }
 
@@ -39,13 +48,88 @@ async fn main() {
     let wallet_address = std::env::var("WALLET_ADDRESS")
         .expect("WALLET_ADDRESS must be set in .env");
 
-    println!("✅ Database initialized!");
+    println!("✅ Database ready!");
     println!("💳 Merchant wallet: {}", wallet_address);
 
+    // Load merchant logo for QR codes (optional)
+    let merchant_logo = match config.merchant_logo_path.as_deref() {
+        Some(path) => match std::fs::read(path) {
+            Ok(bytes) => {
+                println!("🖼️  Merchant logo loaded from: {}", path);
+                Some(Arc::new(bytes))
+            }
+            Err(e) => {
+                eprintln!("⚠️  Could not read merchant logo {}: {}", path, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Load fee payer wallet for gasless checkout (optional)
+    let sponsor_mode = match SponsorMode::parse(&config.fee_sponsor_mode) {
+        Some(mode) => mode,
+        None => {
+            eprintln!("❌ Invalid FEE_SPONSOR_MODE: {} (expected off, spl or all)", config.fee_sponsor_mode);
+            std::process::exit(1);
+        }
+    };
+
+    let fee_sponsor = match (sponsor_mode, config.fee_payer_keypair_path.as_deref()) {
+        (SponsorMode::Off, _) => None,
+        (_, None) => {
+            eprintln!("❌ FEE_SPONSOR_MODE is enabled but FEE_PAYER_KEYPAIR_PATH is not set");
+            std::process::exit(1);
+        }
+        (mode, Some(path)) => match utils::wallet::load_wallet(path) {
+            Ok(keypair) => {
+                let sponsor = FeeSponsor::new(keypair, mode, config.fee_sponsor_daily_limit_lamports);
+                println!("⛽ Fee sponsorship enabled - fee payer: {}", sponsor.pubkey());
+                Some(Arc::new(sponsor))
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to load fee payer wallet: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    // Fan out payment status changes from Redis to SSE/WebSocket clients
+    // Without Redis the streams fall back to re-reading the status periodically
+    let (events, _) = broadcast::channel(1024);
+    if config.uses_redis() {
+        services::events::spawn_event_listener(config.redis_url.clone(), events.clone());
+    } else {
+        println!("ℹ️  No REDIS_URL - live payment updates poll the database");
+    }
+
+    // Test and replay deliveries from the webhook API (events go through the worker)
+    let webhooks = match WebhookDispatcher::new(
+        db.pool.clone(),
+        std::time::Duration::from_secs(config.webhook_timeout_secs),
+        config.webhook_allow_private_hosts,
+    ) {
+        Ok(dispatcher) => dispatcher,
+        Err(e) => {
+            eprintln!("❌ Failed to create webhook client: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Create app state
     let state = api::payments::AppState {
+        payments: Arc::new(PgPaymentRepository::new(db.pool.clone())),
         db,
         wallet_address,
+        order_id_unique: config.order_id_unique,
+        merchant_label: config.merchant_label.clone(),
+        merchant_logo,
+        merchant_icon_url: config.merchant_icon_url.clone(),
+        blockhash_source: Arc::new(RpcBlockhashSource::new(&config.solana_rpc_url)),
+        fee_sponsor,
+        events,
+        webhooks,
+        jwt_secret: config.jwt_secret.clone(),
     };
 
     
@@ -62,9 +146,51 @@ async fn main() {
     println!("\n🚀 API Server running on http://{}", addr);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("📡 POST /payments/create - Create payment request");
-    println!("📡 GET  /payments/:id    - Check payment status");
+    println!("📡 GET  /payments/:id    - Check payment status (?wait=N to long-poll)");
+    println!("📡 POST /payments/status - Batch status lookup");
     println!("📡 GET  /payments        - List all payments");
+    println!("📡 GET  /payments/:id/events - Live status (SSE)");
+    println!("📡 GET  /payments/:id/ws     - Live status (WebSocket)");
+    println!("📡 GET  /payments/:id/qr.svg - QR code (SVG)");
+    println!("📡 GET  /payments/:id/qr.png - QR code (PNG)");
+    println!("📡 GET/POST /payments/:id/transaction-request - Solana Pay transaction request");
+    println!("📡 GET  /payments/by-order/:order_id - Lookup by order ID");
+    println!("📡 GET  /sponsored-fees  - Sponsored fee accounting");
+    println!("📡 GET  /events          - Event feed (?after=<cursor>&types=...)");
+    println!("📡 GET/POST /webhooks    - Webhook endpoints");
+    println!("📡 GET/PATCH/DELETE /webhooks/:id - Manage a webhook endpoint");
+    println!("📡 POST /webhooks/:id/rotate-secret - Rotate signing secret");
+    println!("📡 POST /webhooks/:id/test          - Send a test event");
+    println!("📡 GET  /webhooks/:id/deliveries    - Delivery log");
+    println!("📡 POST /webhook-deliveries/:id/replay - Redeliver an event");
+    println!("📡 GET  /payments/by-memo/:memo      - Lookup by memo");
+    println!("📡 GET  /payments/by-tx/:signature   - Lookup by transaction");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
-    axum::serve(listener, app).await.unwrap();
+    // Stop accepting connections on SIGINT/SIGTERM and let in-flight requests finish
+    let (stopping_tx, mut stopping) = watch::channel(false);
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        utils::shutdown::shutdown_signal().await;
+        println!("\n🛑 Shutdown requested - draining open connections...");
+        let _ = stopping_tx.send(true);
+    });
+
+    // SSE and WebSocket clients never hang up on their own, so the drain is capped
+    let drain_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    tokio::select! {
+        result = server.into_future() => {
+            if let Err(e) = result {
+                eprintln!("❌ Server error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        _ = async {
+            let _ = stopping.wait_for(|stopping| *stopping).await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            eprintln!("⚠️  Connections still open after {}s (live SSE/WebSocket clients), closing them", config.shutdown_timeout_secs);
+        }
+    }
+
+    println!("👋 API server stopped");
 }
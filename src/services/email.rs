@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::database::models::PaymentRequest;
+
+/// Sends payment receipts as a JSON POST to an HTTP email API (RECEIPT_EMAIL_URL)
+/// Without one configured, receipts are only logged
+#[derive(Clone)]
+pub struct ReceiptMailer {
+    client: reqwest::Client,
+    api_url: Option<String>,
+    from: String,
+}
+
+/// Message body posted to the email API
+#[derive(Debug, Serialize)]
+struct EmailMessage<'a> {
+    from: &'a str,
+    to: &'a str,
+    subject: String,
+    text: String,
+}
+
+impl ReceiptMailer {
+    pub fn new(api_url: Option<String>, from: String) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("payment-gateway-rust-receipts")
+            .build()?;
+
+        Ok(ReceiptMailer { client, api_url, from })
+    }
+
+    /// Email a receipt to the payment's customer
+    /// Returns Ok without sending when the payment has no customer email
+    pub async fn send_receipt(&self, payment: &PaymentRequest) -> Result<(), String> {
+        let Some(to) = payment.customer_email.as_deref() else {
+            return Ok(());
+        };
+
+        let message = EmailMessage {
+            from: &self.from,
+            to,
+            subject: format!("Payment receipt {}", payment.order_id.as_deref().unwrap_or(&payment.memo)),
+            text: receipt_text(payment),
+        };
+
+        let Some(api_url) = &self.api_url else {
+            println!("📧 Receipt for payment {} to {} (RECEIPT_EMAIL_URL not set, not sent)", payment.id, to);
+            return Ok(());
+        };
+
+        let response = self
+            .client
+            .post(api_url)
+            .json(&message)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("Email API returned HTTP {}", response.status()));
+        }
+
+        println!("📧 Receipt for payment {} sent to {}", payment.id, to);
+
+        Ok(())
+    }
+}
+
+/// Plain-text receipt body
+fn receipt_text(payment: &PaymentRequest) -> String {
+    let mut lines = vec![
+        format!("Payment {}", payment.id),
+        format!("Status: {}", payment.status),
+        format!("Amount: {} SOL", payment.amount_lamports as f64 / 1_000_000_000.0),
+        format!("Memo: {}", payment.memo),
+    ];
+    if let Some(order_id) = &payment.order_id {
+        lines.push(format!("Order: {}", order_id));
+    }
+    if let Some(tx_sig) = &payment.tx_sig {
+        lines.push(format!("Transaction: {}", tx_sig));
+    }
+    if let Some(paid_at) = payment.paid_at {
+        lines.push(format!("Paid at: {}", paid_at));
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use redis::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::database::models::PaymentRequest;
+
+/// Redis pub/sub channel carrying payment status changes between processes
+pub const PAYMENT_EVENTS_CHANNEL: &str = "payment_events";
+
+/// Bounds of the listener's reconnect backoff
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(60);
+
+/// Payment status change, published by the worker and pushed to API clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentEvent {
+    pub payment_id: Uuid,
+    pub status: String,
+    pub tx_sig: Option<String>,
+    pub sender_address: Option<String>,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl PaymentEvent {
+    /// Status no longer changes once a payment reaches one of these
+    pub fn is_final(&self) -> bool {
+        is_final_status(&self.status)
+    }
+}
+
+/// Snapshot of a stored payment's current status
+impl From<&PaymentRequest> for PaymentEvent {
+    fn from(payment: &PaymentRequest) -> Self {
+        PaymentEvent {
+            payment_id: payment.id,
+            status: payment.status.clone(),
+            tx_sig: payment.tx_sig.clone(),
+            sender_address: payment.sender_address.clone(),
+            paid_at: payment.paid_at,
+            occurred_at: payment.updated_at,
+        }
+    }
+}
+
+/// Final payment statuses
+pub fn is_final_status(status: &str) -> bool {
+    matches!(status, "confirmed" | "underpaid" | "expired" | "failed")
+}
+
+/// Forward payment events from Redis pub/sub into an in-process broadcast channel
+/// One subscription per API instance, fanned out to every SSE/WebSocket client.
+/// Reconnects back off exponentially while Redis stays unreachable; streams
+/// re-read the database for anything published in the meantime
+pub fn spawn_event_listener(redis_url: String, sender: broadcast::Sender<PaymentEvent>) {
+    tokio::spawn(async move {
+        let mut delay = RECONNECT_DELAY_MIN;
+        loop {
+            match listen(&redis_url, &sender).await {
+                Ok(()) => {
+                    eprintln!("⚠️  Payment event subscription closed, reconnecting...");
+                    delay = RECONNECT_DELAY_MIN;
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    eprintln!("❌ Payment event subscription error: {} (retrying in {}s)", e, delay.as_secs());
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+                }
+            }
+        }
+    });
+}
+
+async fn listen(
+    redis_url: &str,
+    sender: &broadcast::Sender<PaymentEvent>,
+) -> Result<(), redis::RedisError> {
+    let client = Client::open(redis_url)?;
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(PAYMENT_EVENTS_CHANNEL).await?;
+
+    println!("📡 Subscribed to payment events");
+
+    let mut messages = pubsub.into_on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = match msg.get_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                eprintln!("⚠️  Unreadable payment event: {}", e);
+                continue;
+            }
+        };
+
+        match serde_json::from_str::<PaymentEvent>(&payload) {
+            // No receivers just means nobody is watching right now
+            Ok(event) => {
+                let _ = sender.send(event);
+            }
+            Err(e) => eprintln!("⚠️  Malformed payment event: {}", e),
+        }
+    }
+
+    Ok(())
+}
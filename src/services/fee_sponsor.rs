@@ -0,0 +1,75 @@
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+use crate::config::tokens::TokenInfo;
+
+/// Base fee Solana charges per signature
+pub const LAMPORTS_PER_SIGNATURE: i64 = 5_000;
+
+/// Which payments get their network fee sponsored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SponsorMode {
+    Off,
+    /// Only SPL token payments (customers paying USDC may hold no SOL)
+    SplOnly,
+    All,
+}
+
+impl SponsorMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "off" | "false" | "none" => Some(SponsorMode::Off),
+            "spl" | "spl_only" => Some(SponsorMode::SplOnly),
+            "all" => Some(SponsorMode::All),
+            _ => None,
+        }
+    }
+}
+
+/// Merchant hot wallet that pays network fees for checkout transactions
+pub struct FeeSponsor {
+    keypair: Keypair,
+    mode: SponsorMode,
+    /// Max sponsored fees per merchant in any rolling 24h window
+    pub daily_limit_lamports: i64,
+}
+
+impl FeeSponsor {
+    pub fn new(keypair: Keypair, mode: SponsorMode, daily_limit_lamports: i64) -> Self {
+        FeeSponsor {
+            keypair,
+            mode,
+            daily_limit_lamports,
+        }
+    }
+
+    /// Fee payer public key
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    /// Should a payment in this token be sponsored?
+    pub fn covers(&self, token: &TokenInfo) -> bool {
+        match self.mode {
+            SponsorMode::Off => false,
+            SponsorMode::SplOnly => token.mint.is_some(),
+            SponsorMode::All => true,
+        }
+    }
+
+    /// Add the fee payer signature; the customer signature slot stays empty
+    pub fn partial_sign(&self, transaction: &mut Transaction) -> Result<(), String> {
+        let blockhash = transaction.message.recent_blockhash;
+        transaction
+            .try_partial_sign(&[&self.keypair], blockhash)
+            .map_err(|e| format!("Fee payer failed to sign: {}", e))
+    }
+}
+
+/// Network fee the fee payer will be charged for a transaction
+pub fn estimate_fee(transaction: &Transaction) -> i64 {
+    transaction.message.header.num_required_signatures as i64 * LAMPORTS_PER_SIGNATURE
+}
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::queue::{
+    CONFIRMATION_QUEUE, EMAIL_QUEUE, EXPIRY_QUEUE, PAYMENT_EXPIRY_QUEUE, RECONCILIATION_QUEUE, WEBHOOK_QUEUE,
+};
+use super::webhooks::WebhookEvent;
+use crate::config::tokens::find_token;
+
+/// Job payload carried on a queue
+/// `JOB_TYPE` and `VERSION` go into the envelope; a payload change that
+/// old workers can't read must bump `VERSION`
+/// Each job type has its own `QUEUE`, so workers can run a subset of types
+pub trait QueueJob: Serialize + DeserializeOwned {
+    const JOB_TYPE: &'static str;
+    const VERSION: u32;
+    const QUEUE: &'static str;
+
+    /// Reject payloads that deserialize but can't be processed
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Envelope every queued job is wrapped in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobEnvelope {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub job_type: String,
+    pub version: u32,
+    pub enqueued_at: DateTime<Utc>,
+    pub payload: Value,
+}
+
+impl JobEnvelope {
+    pub fn new<J: QueueJob>(job: &J) -> Result<Self, JobError> {
+        job.validate().map_err(JobError::Invalid)?;
+
+        Ok(JobEnvelope {
+            id: Uuid::new_v4().to_string(),
+            job_type: J::JOB_TYPE.to_string(),
+            version: J::VERSION,
+            enqueued_at: Utc::now(),
+            payload: serde_json::to_value(job).map_err(|e| JobError::Malformed(e.to_string()))?,
+        })
+    }
+
+    /// Same envelope under a caller-chosen id, for jobs that are looked up or cancelled later
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Typed payload, checking type, version and contents
+    pub fn decode<J: QueueJob>(&self) -> Result<J, JobError> {
+        if self.job_type != J::JOB_TYPE {
+            return Err(JobError::WrongType {
+                expected: J::JOB_TYPE,
+                found: self.job_type.clone(),
+            });
+        }
+        if self.version != J::VERSION {
+            return Err(JobError::UnsupportedVersion {
+                job_type: J::JOB_TYPE,
+                expected: J::VERSION,
+                found: self.version,
+            });
+        }
+
+        let job: J = serde_json::from_value(self.payload.clone())
+            .map_err(|e| JobError::Malformed(e.to_string()))?;
+        job.validate().map_err(JobError::Invalid)?;
+
+        Ok(job)
+    }
+}
+
+/// Why a queued job can't be processed - never fixed by retrying
+#[derive(Debug, Clone)]
+pub enum JobError {
+    /// Not a job envelope at all (e.g. queued by an older release)
+    Unversioned,
+    WrongType { expected: &'static str, found: String },
+    UnsupportedVersion { job_type: &'static str, expected: u32, found: u32 },
+    /// Payload missing fields or of the wrong shape
+    Malformed(String),
+    /// Payload well-formed but with unusable values
+    Invalid(String),
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::Unversioned => write!(f, "Job has no type/version envelope"),
+            JobError::WrongType { expected, found } => {
+                write!(f, "Expected a {} job, got {}", expected, found)
+            }
+            JobError::UnsupportedVersion { job_type, expected, found } => {
+                write!(f, "Unsupported {} job version {} (expected {})", job_type, found, expected)
+            }
+            JobError::Malformed(e) => write!(f, "Malformed job payload: {}", e),
+            JobError::Invalid(e) => write!(f, "Invalid job payload: {}", e),
+        }
+    }
+}
+
+/// Payment confirmation job - pushed by the indexer, handled by the worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaymentConfirmationJob {
+    pub job_id: String,
+    pub payment_id: Uuid,
+    pub memo: String,
+    pub sender_address: String,
+    pub tx_sig: String,
+    /// Token that was paid - must match the payment's token
+    pub token_symbol: String,
+    /// Amount received, in the token's base units (lamports for SOL)
+    pub amount_lamports: i64,
+    /// Who paid the network fee, and how much - settles a sponsored fee reservation
+    pub fee_payer: String,
+    pub fee_lamports: i64,
+    pub paid_at: Option<DateTime<Utc>>,
+}
+
+impl QueueJob for PaymentConfirmationJob {
+    const JOB_TYPE: &'static str = "payment_confirmation";
+    const VERSION: u32 = 3;
+    const QUEUE: &'static str = CONFIRMATION_QUEUE;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.memo.trim().is_empty() {
+            return Err("memo is empty".to_string());
+        }
+        if Pubkey::from_str(&self.sender_address).is_err() {
+            return Err(format!("sender_address '{}' is not a valid public key", self.sender_address));
+        }
+        if Signature::from_str(&self.tx_sig).is_err() {
+            return Err(format!("tx_sig '{}' is not a valid signature", self.tx_sig));
+        }
+        if find_token(&self.token_symbol).is_none() {
+            return Err(format!("token_symbol '{}' is not a supported token", self.token_symbol));
+        }
+        if self.amount_lamports <= 0 {
+            return Err(format!("amount_lamports {} must be positive", self.amount_lamports));
+        }
+        if Pubkey::from_str(&self.fee_payer).is_err() {
+            return Err(format!("fee_payer '{}' is not a valid public key", self.fee_payer));
+        }
+        if self.fee_lamports < 0 {
+            return Err(format!("fee_lamports {} is negative", self.fee_lamports));
+        }
+
+        Ok(())
+    }
+}
+
+/// One webhook event to one endpoint - a single attempt per delivery,
+/// with backoff between attempts coming from the webhook queue's retry policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookDeliveryJob {
+    pub endpoint_id: Uuid,
+    pub event: WebhookEvent,
+}
+
+impl QueueJob for WebhookDeliveryJob {
+    const JOB_TYPE: &'static str = "webhook_delivery";
+    const VERSION: u32 = 1;
+    const QUEUE: &'static str = WEBHOOK_QUEUE;
+}
+
+/// Receipt email for a settled payment with a customer email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailReceiptJob {
+    pub payment_id: Uuid,
+}
+
+impl QueueJob for EmailReceiptJob {
+    const JOB_TYPE: &'static str = "email_receipt";
+    const VERSION: u32 = 1;
+    const QUEUE: &'static str = EMAIL_QUEUE;
+}
+
+/// Expire one payment at its expiry time - scheduled when the payment is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaymentExpiryJob {
+    pub payment_id: Uuid,
+}
+
+impl PaymentExpiryJob {
+    /// Envelope id of a payment's expiry job, so confirming the payment can cancel it
+    pub fn job_id(payment_id: Uuid) -> String {
+        format!("expire-{}", payment_id)
+    }
+
+    /// Envelope carrying the payment's expiry job under its fixed id
+    pub fn envelope(payment_id: Uuid) -> Result<JobEnvelope, JobError> {
+        Ok(JobEnvelope::new(&PaymentExpiryJob { payment_id })?.with_id(PaymentExpiryJob::job_id(payment_id)))
+    }
+}
+
+impl QueueJob for PaymentExpiryJob {
+    const JOB_TYPE: &'static str = "payment_expiry";
+    const VERSION: u32 = 1;
+    const QUEUE: &'static str = PAYMENT_EXPIRY_QUEUE;
+}
+
+/// Mark every pending payment past its expiry time expired
+/// A safety net behind the per-payment expiry jobs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpirySweepJob {}
+
+impl QueueJob for ExpirySweepJob {
+    const JOB_TYPE: &'static str = "expiry_sweep";
+    const VERSION: u32 = 1;
+    const QUEUE: &'static str = EXPIRY_QUEUE;
+}
+
+/// Re-scan the wallet's recent transactions for payments the indexer missed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReconciliationJob {
+    /// How many recent signatures to check
+    pub signature_limit: usize,
+}
+
+impl QueueJob for ReconciliationJob {
+    const JOB_TYPE: &'static str = "reconciliation";
+    const VERSION: u32 = 1;
+    const QUEUE: &'static str = RECONCILIATION_QUEUE;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.signature_limit == 0 || self.signature_limit > 1000 {
+            return Err(format!("signature_limit {} must be between 1 and 1000", self.signature_limit));
+        }
+
+        Ok(())
+    }
+}
@@ -1 +1,9 @@
+pub mod email;
+pub mod events;
+pub mod fee_sponsor;
+pub mod jobs;
+pub mod outbox;
 pub mod queue;
+pub mod retry;
+pub mod transaction_builder;
+pub mod webhooks;
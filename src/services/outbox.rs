@@ -0,0 +1,85 @@
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::database::outbox::{lock_unsent, mark_failed, mark_sent, prune_sent};
+use crate::services::queue::{JobQueue, QueueError};
+
+/// Most outbox entries relayed per transaction
+const RELAY_BATCH_SIZE: i64 = 100;
+
+/// How often the outbox is checked for unsent entries
+const RELAY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often relayed entries past retention are deleted
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Relayed entries are kept this long for debugging
+const SENT_RETENTION_DAYS: i64 = 7;
+
+/// Publishes outbox rows to the job queue and marks them sent
+/// Delivery is at least once: a crash after publishing but before the commit
+/// publishes the entry again with the same envelope id
+pub struct OutboxRelay {
+    pool: PgPool,
+    queue: Box<dyn JobQueue>,
+}
+
+impl OutboxRelay {
+    pub fn new(pool: PgPool, queue: Box<dyn JobQueue>) -> Self {
+        OutboxRelay { pool, queue }
+    }
+
+    /// Relay one batch of unsent entries in order, returning how many were sent
+    /// Stops at the first queue failure so later entries don't overtake it
+    pub async fn relay_batch(&mut self) -> Result<usize, QueueError> {
+        let mut tx = self.pool.begin().await?;
+        let entries = lock_unsent(&mut tx, RELAY_BATCH_SIZE).await?;
+        let mut sent = 0;
+
+        for entry in &entries {
+            let queued = match entry.run_at {
+                Some(run_at) => self.queue.enqueue_at(&entry.queue, &entry.envelope, run_at).await,
+                None => self.queue.enqueue(&entry.queue, &entry.envelope).await,
+            };
+
+            match queued {
+                Ok(()) => {
+                    mark_sent(&mut tx, entry.id).await?;
+                    sent += 1;
+                }
+                Err(e) => {
+                    eprintln!("❌ Outbox entry {} not relayed (attempt {}): {}", entry.id, entry.attempts + 1, e);
+                    mark_failed(&mut tx, entry.id, &e.to_string()).await?;
+                    break;
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(sent)
+    }
+
+    /// Relay continuously in the background and prune old sent entries
+    pub fn spawn(mut self) {
+        tokio::spawn(async move {
+            let mut relay = tokio::time::interval(RELAY_INTERVAL);
+            let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = relay.tick() => match self.relay_batch().await {
+                        Ok(0) => {}
+                        Ok(count) => println!("📤 Outbox relayed {} job(s)", count),
+                        Err(e) => eprintln!("❌ Outbox relay failed: {}", e),
+                    },
+                    _ = prune.tick() => match prune_sent(&self.pool, SENT_RETENTION_DAYS).await {
+                        Ok(0) => {}
+                        Ok(count) => println!("🧹 Pruned {} relayed outbox entries", count),
+                        Err(e) => eprintln!("❌ Outbox pruning failed: {}", e),
+                    },
+                }
+            }
+        });
+    }
+}
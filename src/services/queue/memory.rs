@@ -0,0 +1,558 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use super::{
+    exhausted_error, job_id, DeadLetter, JobQueue, QueueError, QueueMetrics, QueueSettings, ReservedJob, ScheduledJob,
+};
+use crate::services::jobs::JobEnvelope;
+use crate::services::retry::RetryPolicy;
+
+/// In-process queue for tests
+/// Clones share the same jobs; nothing survives a restart, and other processes
+/// can't see it, so QUEUE_BACKEND doesn't offer it
+#[derive(Clone, Default)]
+pub struct MemoryQueue {
+    state: Arc<Mutex<MemoryState>>,
+    /// Wakes reservers blocked on an empty queue
+    pushed: Arc<Notify>,
+    settings: QueueSettings,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    queues: HashMap<String, QueueState>,
+    /// Processed job ids and when they stop counting
+    processed: HashMap<String, DateTime<Utc>>,
+    next_receipt: u64,
+}
+
+/// Jobs on one queue, mirroring the Redis list backend
+#[derive(Default)]
+struct QueueState {
+    /// Pushed at the front, reserved from the back
+    ready: VecDeque<String>,
+    /// Reserved jobs by receipt, with their lease deadline
+    in_flight: HashMap<String, (String, Instant)>,
+    /// Jobs waiting out their retry backoff or scheduled for later, with their due time
+    delayed: Vec<(Instant, String)>,
+    attempts: HashMap<String, i64>,
+    errors: HashMap<String, String>,
+    /// Newest first
+    dead: VecDeque<DeadLetter>,
+}
+
+impl MemoryQueue {
+    pub fn new() -> Self {
+        MemoryQueue::default()
+    }
+
+    pub fn with_settings(mut self, settings: QueueSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        // A panic mid-update can't leave the maps half-written in a way that matters here
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn push_raw(&self, queue: &str, raw: String) {
+        let mut state = self.lock();
+        state.queues.entry(queue.to_string()).or_default().ready.push_front(raw);
+        drop(state);
+
+        self.pushed.notify_waiters();
+    }
+
+    /// Take the next ready job, or dead-letter it if it has been delivered too often
+    fn take_next(&self, queue: &str) -> Option<ReservedJob> {
+        let lease = Duration::from_secs(self.settings.visibility_timeout_secs);
+        let max_attempts = self.retry_policy(queue).max_attempts;
+
+        let mut state = self.lock();
+        state.next_receipt += 1;
+        let receipt = state.next_receipt.to_string();
+        let jobs = state.queues.entry(queue.to_string()).or_default();
+
+        let raw = jobs.ready.pop_back()?;
+        let mut job = ReservedJob::new(queue, raw.clone(), 0, Some(receipt.clone()));
+        let attempts = jobs.attempts.entry(job.id.clone()).or_default();
+        *attempts += 1;
+        job.attempts = *attempts;
+
+        // Redelivered by reclaim more often than the policy allows
+        if job.attempts > max_attempts as i64 {
+            let error = exhausted_error(max_attempts, jobs.errors.remove(&job.id));
+            jobs.attempts.remove(&job.id);
+            jobs.dead.push_front(job.dead_letter(&error));
+            eprintln!("☠️  Job {} dead-lettered on {}: {}", job.id, queue, error);
+            return None;
+        }
+
+        jobs.in_flight.insert(receipt, (raw, Instant::now() + lease));
+
+        Some(job)
+    }
+
+    /// Take a job off the in-flight set, returning false if it was no longer there
+    fn release(jobs: &mut QueueState, job: &ReservedJob) -> bool {
+        job.receipt
+            .as_ref()
+            .and_then(|receipt| jobs.in_flight.remove(receipt))
+            .is_some()
+    }
+}
+
+#[async_trait]
+impl JobQueue for MemoryQueue {
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn retry_policy(&self, queue: &str) -> RetryPolicy {
+        self.settings.retry_policy(queue)
+    }
+
+    fn clone_queue(&self) -> Box<dyn JobQueue> {
+        Box::new(self.clone())
+    }
+
+    async fn enqueue(&mut self, queue: &str, envelope: &JobEnvelope) -> Result<(), QueueError> {
+        self.push_raw(queue, serde_json::to_string(envelope)?);
+
+        Ok(())
+    }
+
+    async fn enqueue_at(&mut self, queue: &str, envelope: &JobEnvelope, run_at: DateTime<Utc>) -> Result<(), QueueError> {
+        let raw = serde_json::to_string(envelope)?;
+
+        // Negative means it's already due
+        let Ok(delay) = (run_at - Utc::now()).to_std() else {
+            self.push_raw(queue, raw);
+            return Ok(());
+        };
+
+        let mut state = self.lock();
+        state.queues.entry(queue.to_string()).or_default().delayed.push((Instant::now() + delay, raw));
+
+        Ok(())
+    }
+
+    async fn list_scheduled(&mut self, queue: &str, limit: isize) -> Result<Vec<ScheduledJob>, QueueError> {
+        let now = Instant::now();
+        let state = self.lock();
+
+        let mut waiting: Vec<&(Instant, String)> = state
+            .queues
+            .get(queue)
+            .map(|jobs| jobs.delayed.iter().collect())
+            .unwrap_or_default();
+        waiting.sort_by_key(|(at, _)| *at);
+
+        Ok(waiting
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(at, raw)| {
+                let remaining = ChronoDuration::from_std(at.saturating_duration_since(now)).unwrap_or_default();
+                ScheduledJob::new(queue, raw, Utc::now() + remaining)
+            })
+            .collect())
+    }
+
+    async fn cancel_scheduled(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let mut state = self.lock();
+        let jobs = state.queues.entry(queue.to_string()).or_default();
+
+        match jobs.delayed.iter().position(|(_, raw)| job_id(serde_json::from_str(raw).ok().as_ref(), raw) == id) {
+            Some(pos) => {
+                jobs.delayed.remove(pos);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn reserve(&mut self, queue: &str, timeout_secs: f64) -> Result<Option<ReservedJob>, QueueError> {
+        let deadline = Instant::now() + Duration::from_secs_f64(timeout_secs.max(0.0));
+
+        loop {
+            // Registered before checking, so a push in between isn't missed
+            let pushed = self.pushed.notified();
+            tokio::pin!(pushed);
+            pushed.as_mut().enable();
+
+            let ready = self.lock().queues.get(queue).is_some_and(|jobs| !jobs.ready.is_empty());
+            if ready {
+                return Ok(self.take_next(queue).inspect(|job| {
+                    println!("📥 Job reserved from {}: {} (attempt {})", queue, job.id, job.attempts);
+                }));
+            }
+
+            if tokio::time::timeout_at(deadline, pushed).await.is_err() {
+                return Ok(None);
+            }
+        }
+    }
+
+    async fn ack(&mut self, job: &ReservedJob) -> Result<(), QueueError> {
+        let mut state = self.lock();
+        let jobs = state.queues.entry(job.queue.clone()).or_default();
+
+        if Self::release(jobs, job) {
+            jobs.attempts.remove(&job.id);
+            jobs.errors.remove(&job.id);
+        }
+
+        Ok(())
+    }
+
+    async fn retry_later(&mut self, job: &ReservedJob, error: &str) -> Result<bool, QueueError> {
+        let policy = self.retry_policy(&job.queue);
+
+        if job.attempts >= policy.max_attempts as i64 {
+            self.dead_letter(job, error).await?;
+            return Ok(true);
+        }
+
+        let delay = policy.backoff(job.attempts as u32);
+
+        let mut state = self.lock();
+        let jobs = state.queues.entry(job.queue.clone()).or_default();
+        if Self::release(jobs, job) {
+            jobs.delayed.push((Instant::now() + delay, job.raw.clone()));
+            jobs.errors.insert(job.id.clone(), error.to_string());
+        }
+        drop(state);
+
+        println!("🔁 Job {} retrying in {}s (attempt {}/{})", job.id, delay.as_secs(), job.attempts, policy.max_attempts);
+
+        Ok(false)
+    }
+
+    async fn dead_letter(&mut self, job: &ReservedJob, error: &str) -> Result<(), QueueError> {
+        let mut state = self.lock();
+        let jobs = state.queues.entry(job.queue.clone()).or_default();
+        if Self::release(jobs, job) {
+            jobs.attempts.remove(&job.id);
+            jobs.errors.remove(&job.id);
+            jobs.dead.push_front(job.dead_letter(error));
+        }
+        drop(state);
+
+        eprintln!("☠️  Job {} dead-lettered on {}: {}", job.id, job.queue, error);
+
+        Ok(())
+    }
+
+    async fn reclaim_stale_jobs(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let now = Instant::now();
+        let mut state = self.lock();
+        let jobs = state.queues.entry(queue.to_string()).or_default();
+
+        let expired: Vec<String> = jobs
+            .in_flight
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(receipt, _)| receipt.clone())
+            .collect();
+
+        for receipt in &expired {
+            if let Some((raw, _)) = jobs.in_flight.remove(receipt) {
+                jobs.ready.push_back(raw);
+            }
+        }
+        drop(state);
+
+        if !expired.is_empty() {
+            println!("♻️  Reclaimed {} stale job(s) on {}", expired.len(), queue);
+            self.pushed.notify_waiters();
+        }
+
+        Ok(expired.len())
+    }
+
+    async fn promote_delayed_jobs(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let now = Instant::now();
+        let mut state = self.lock();
+        let jobs = state.queues.entry(queue.to_string()).or_default();
+
+        let (due, waiting): (Vec<_>, Vec<_>) = jobs.delayed.drain(..).partition(|(at, _)| *at <= now);
+        jobs.delayed = waiting;
+        let promoted = due.len();
+        for (_, raw) in due {
+            jobs.ready.push_back(raw);
+        }
+        drop(state);
+
+        if promoted > 0 {
+            self.pushed.notify_waiters();
+        }
+
+        Ok(promoted)
+    }
+
+    async fn metrics(&mut self, queue: &str) -> Result<QueueMetrics, QueueError> {
+        let state = self.lock();
+        let (ready, in_flight, delayed, dead) = state
+            .queues
+            .get(queue)
+            .map(|jobs| (jobs.ready.len(), jobs.in_flight.len(), jobs.delayed.len(), jobs.dead.len()))
+            .unwrap_or_default();
+
+        Ok(QueueMetrics {
+            queue: queue.to_string(),
+            backend: self.backend_name(),
+            ready: Some(ready as u64),
+            in_flight: in_flight as u64,
+            delayed: delayed as u64,
+            dead: dead as u64,
+            consumers: None,
+        })
+    }
+
+    async fn list_dead_letters(&mut self, queue: &str, offset: isize, limit: isize) -> Result<Vec<DeadLetter>, QueueError> {
+        let state = self.lock();
+
+        Ok(state
+            .queues
+            .get(queue)
+            .map(|jobs| {
+                jobs.dead
+                    .iter()
+                    .skip(offset.max(0) as usize)
+                    .take(limit.max(0) as usize)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn dead_letter_count(&mut self, queue: &str) -> Result<usize, QueueError> {
+        Ok(self.lock().queues.get(queue).map_or(0, |jobs| jobs.dead.len()))
+    }
+
+    async fn requeue_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let letter = {
+            let mut state = self.lock();
+            let jobs = state.queues.entry(queue.to_string()).or_default();
+            match jobs.dead.iter().position(|letter| letter.id == id) {
+                Some(pos) => jobs.dead.remove(pos),
+                None => None,
+            }
+        };
+
+        let Some(letter) = letter else {
+            return Ok(false);
+        };
+
+        self.push_raw(queue, letter.requeue_entry()?);
+        println!("📤 Dead letter {} requeued on {}", id, queue);
+
+        Ok(true)
+    }
+
+    async fn requeue_all_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let letters: Vec<DeadLetter> = {
+            let mut state = self.lock();
+            let jobs = state.queues.entry(queue.to_string()).or_default();
+            jobs.dead.drain(..).collect()
+        };
+
+        let requeued = letters.len();
+        for letter in letters {
+            self.push_raw(queue, letter.requeue_entry()?);
+        }
+
+        Ok(requeued)
+    }
+
+    async fn purge_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let mut state = self.lock();
+        let jobs = state.queues.entry(queue.to_string()).or_default();
+
+        match jobs.dead.iter().position(|letter| letter.id == id) {
+            Some(pos) => Ok(jobs.dead.remove(pos).is_some()),
+            None => Ok(false),
+        }
+    }
+
+    async fn purge_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let mut state = self.lock();
+        let jobs = state.queues.entry(queue.to_string()).or_default();
+        let count = jobs.dead.len();
+        jobs.dead.clear();
+
+        Ok(count)
+    }
+
+    async fn is_job_processed(&mut self, job_id: &str) -> Result<bool, QueueError> {
+        let state = self.lock();
+
+        Ok(state.processed.get(job_id).is_some_and(|until| *until > Utc::now()))
+    }
+
+    async fn mark_job_processed(&mut self, job_id: &str) -> Result<(), QueueError> {
+        let now = Utc::now();
+        let mut state = self.lock();
+        state.processed.retain(|_, until| *until > now);
+        state.processed.insert(job_id.to_string(), now + ChronoDuration::hours(24));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::jobs::PaymentExpiryJob;
+    use uuid::Uuid;
+
+    const QUEUE: &str = "test_queue";
+
+    /// Two attempts with no backoff, so retries are due immediately
+    fn queue() -> MemoryQueue {
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        MemoryQueue::new().with_settings(QueueSettings::default().with_retry_policy(QUEUE, policy))
+    }
+
+    fn envelope() -> JobEnvelope {
+        PaymentExpiryJob::envelope(Uuid::new_v4()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reserve_then_ack_removes_job() {
+        let mut queue = queue();
+        let envelope = envelope();
+        queue.enqueue(QUEUE, &envelope).await.unwrap();
+
+        let job = queue.reserve(QUEUE, 0.0).await.unwrap().unwrap();
+        assert_eq!(job.id, envelope.id);
+        assert_eq!(job.attempts, 1);
+        assert_eq!(queue.metrics(QUEUE).await.unwrap().in_flight, 1);
+
+        queue.ack(&job).await.unwrap();
+
+        let metrics = queue.metrics(QUEUE).await.unwrap();
+        assert_eq!((metrics.ready, metrics.in_flight), (Some(0), 0));
+        assert!(queue.reserve(QUEUE, 0.0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn jobs_are_reserved_in_push_order() {
+        let mut queue = queue();
+        let (first, second) = (envelope(), envelope());
+        queue.enqueue(QUEUE, &first).await.unwrap();
+        queue.enqueue(QUEUE, &second).await.unwrap();
+
+        assert_eq!(queue.reserve(QUEUE, 0.0).await.unwrap().unwrap().id, first.id);
+        assert_eq!(queue.reserve(QUEUE, 0.0).await.unwrap().unwrap().id, second.id);
+    }
+
+    #[tokio::test]
+    async fn retry_later_delays_then_dead_letters() {
+        let mut queue = queue();
+        let envelope = envelope();
+        queue.enqueue(QUEUE, &envelope).await.unwrap();
+
+        let job = queue.reserve(QUEUE, 0.0).await.unwrap().unwrap();
+        assert!(!queue.retry_later(&job, "first failure").await.unwrap());
+        assert_eq!(queue.metrics(QUEUE).await.unwrap().delayed, 1);
+        assert!(queue.reserve(QUEUE, 0.0).await.unwrap().is_none());
+
+        assert_eq!(queue.promote_delayed_jobs(QUEUE).await.unwrap(), 1);
+        let job = queue.reserve(QUEUE, 0.0).await.unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+
+        // Last allowed attempt - the job goes to the dead-letter list
+        assert!(queue.retry_later(&job, "second failure").await.unwrap());
+        assert_eq!(queue.dead_letter_count(QUEUE).await.unwrap(), 1);
+        let dead = queue.list_dead_letters(QUEUE, 0, 10).await.unwrap();
+        assert_eq!(dead[0].id, envelope.id);
+        assert_eq!(dead[0].attempts, 2);
+        assert_eq!(dead[0].last_error, "second failure");
+
+        let metrics = queue.metrics(QUEUE).await.unwrap();
+        assert_eq!((metrics.in_flight, metrics.delayed, metrics.dead), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn requeued_dead_letter_starts_over() {
+        let mut queue = queue();
+        let envelope = envelope();
+        queue.enqueue(QUEUE, &envelope).await.unwrap();
+
+        let job = queue.reserve(QUEUE, 0.0).await.unwrap().unwrap();
+        queue.dead_letter(&job, "bad payload").await.unwrap();
+        assert!(queue.requeue_dead_letter(QUEUE, &envelope.id).await.unwrap());
+        assert!(!queue.requeue_dead_letter(QUEUE, &envelope.id).await.unwrap());
+
+        let job = queue.reserve(QUEUE, 0.0).await.unwrap().unwrap();
+        assert_eq!(job.id, envelope.id);
+        assert_eq!(job.attempts, 1);
+        assert_eq!(queue.dead_letter_count(QUEUE).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn scheduled_job_waits_until_due_and_can_be_cancelled() {
+        let mut queue = queue();
+        let envelope = envelope();
+        let run_at = Utc::now() + ChronoDuration::hours(1);
+        queue.enqueue_at(QUEUE, &envelope, run_at).await.unwrap();
+
+        let scheduled = queue.list_scheduled(QUEUE, 10).await.unwrap();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].id, envelope.id);
+        assert_eq!(scheduled[0].job_type.as_deref(), Some(envelope.job_type.as_str()));
+        assert!((scheduled[0].run_at - run_at).num_seconds().abs() <= 1);
+
+        assert_eq!(queue.promote_delayed_jobs(QUEUE).await.unwrap(), 0);
+        assert!(queue.reserve(QUEUE, 0.0).await.unwrap().is_none());
+
+        assert!(queue.cancel_scheduled(QUEUE, &envelope.id).await.unwrap());
+        assert!(!queue.cancel_scheduled(QUEUE, &envelope.id).await.unwrap());
+        assert!(queue.list_scheduled(QUEUE, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn job_scheduled_in_the_past_is_ready_now() {
+        let mut queue = queue();
+        let envelope = envelope();
+        queue.enqueue_at(QUEUE, &envelope, Utc::now() - ChronoDuration::minutes(1)).await.unwrap();
+
+        assert!(queue.list_scheduled(QUEUE, 10).await.unwrap().is_empty());
+        assert_eq!(queue.reserve(QUEUE, 0.0).await.unwrap().unwrap().id, envelope.id);
+    }
+
+    #[tokio::test]
+    async fn blocked_reserve_wakes_on_push() {
+        let mut queue = queue();
+        let mut reserver = queue.clone();
+        let waiting = tokio::spawn(async move { reserver.reserve(QUEUE, 5.0).await });
+
+        tokio::task::yield_now().await;
+        let envelope = envelope();
+        queue.enqueue(QUEUE, &envelope).await.unwrap();
+
+        let job = waiting.await.unwrap().unwrap().unwrap();
+        assert_eq!(job.id, envelope.id);
+    }
+
+    #[tokio::test]
+    async fn processed_marks_are_remembered() {
+        let mut queue = queue();
+
+        assert!(!queue.is_job_processed("job-1").await.unwrap());
+        queue.mark_job_processed("job-1").await.unwrap();
+        assert!(queue.is_job_processed("job-1").await.unwrap());
+        assert!(!queue.is_job_processed("job-2").await.unwrap());
+    }
+}
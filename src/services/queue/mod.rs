@@ -0,0 +1,401 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use super::jobs::{JobEnvelope, JobError, PaymentConfirmationJob, QueueJob};
+use super::retry::RetryPolicy;
+use crate::config::Config;
+
+mod memory;
+mod postgres;
+mod redis_queue;
+mod streams;
+
+pub use memory::MemoryQueue;
+pub use postgres::PgQueue;
+pub use redis_queue::{QueueBackend, QueueService};
+pub use streams::DEFAULT_CONSUMER_GROUP;
+
+/// Confirmation jobs waiting for a worker
+pub const CONFIRMATION_QUEUE: &str = "confirmation_queue";
+
+/// Queues for the other job types (see `services::jobs`)
+pub const WEBHOOK_QUEUE: &str = "webhook_queue";
+pub const EMAIL_QUEUE: &str = "email_queue";
+pub const EXPIRY_QUEUE: &str = "expiry_queue";
+pub const PAYMENT_EXPIRY_QUEUE: &str = "payment_expiry_queue";
+pub const RECONCILIATION_QUEUE: &str = "reconciliation_queue";
+
+/// Every queue a job type can use
+pub const QUEUES: [&str; 6] = [
+    CONFIRMATION_QUEUE,
+    WEBHOOK_QUEUE,
+    EMAIL_QUEUE,
+    EXPIRY_QUEUE,
+    PAYMENT_EXPIRY_QUEUE,
+    RECONCILIATION_QUEUE,
+];
+
+/// Default time a reserved job stays invisible before it can be reclaimed
+pub const DEFAULT_VISIBILITY_TIMEOUT_SECS: u64 = 60;
+
+/// Reliable job queue: reserve, then ack, retry later or dead-letter
+/// Implemented on Redis (lists or streams), Postgres and in memory
+#[async_trait]
+pub trait JobQueue: Send {
+    /// Backend name for logs and metrics
+    fn backend_name(&self) -> &'static str;
+
+    fn retry_policy(&self, queue: &str) -> RetryPolicy;
+
+    /// Another handle on the same queue, for a separate task
+    fn clone_queue(&self) -> Box<dyn JobQueue>;
+
+    /// Queue an enveloped job
+    async fn enqueue(&mut self, queue: &str, envelope: &JobEnvelope) -> Result<(), QueueError>;
+
+    /// Queue an enveloped job that becomes available at `run_at`
+    /// Held with delayed retries until the promoter (or the backend) makes it ready
+    async fn enqueue_at(&mut self, queue: &str, envelope: &JobEnvelope, run_at: DateTime<Utc>) -> Result<(), QueueError>;
+
+    /// Jobs waiting for their run time (scheduled or backing off), soonest first
+    async fn list_scheduled(&mut self, queue: &str, limit: isize) -> Result<Vec<ScheduledJob>, QueueError>;
+
+    /// Drop a job that hasn't become available yet
+    /// Returns false if no waiting job has that id (it may already have run)
+    async fn cancel_scheduled(&mut self, queue: &str, id: &str) -> Result<bool, QueueError>;
+
+    /// Reserve the next job, waiting up to the timeout
+    /// The job stays in flight until acked, retried or dead-lettered;
+    /// if the worker dies first it is handed out again after the visibility timeout
+    async fn reserve(&mut self, queue: &str, timeout_secs: f64) -> Result<Option<ReservedJob>, QueueError>;
+
+    /// Acknowledge a handled job, removing it for good
+    async fn ack(&mut self, job: &ReservedJob) -> Result<(), QueueError>;
+
+    /// Schedule a failed job for another attempt after its backoff,
+    /// or dead-letter it once the queue's retry policy is exhausted
+    /// Returns true if the job was dead-lettered
+    async fn retry_later(&mut self, job: &ReservedJob, error: &str) -> Result<bool, QueueError>;
+
+    /// Move a job straight to the dead-letter queue (retrying can't help)
+    async fn dead_letter(&mut self, job: &ReservedJob, error: &str) -> Result<(), QueueError>;
+
+    /// Requeue in-flight jobs whose visibility timeout has passed
+    async fn reclaim_stale_jobs(&mut self, queue: &str) -> Result<usize, QueueError>;
+
+    /// Make delayed and scheduled jobs whose time has come available
+    async fn promote_delayed_jobs(&mut self, queue: &str) -> Result<usize, QueueError>;
+
+    /// Depth, in-flight and lag for a queue
+    async fn metrics(&mut self, queue: &str) -> Result<QueueMetrics, QueueError>;
+
+    /// Dead letters on a queue, newest first
+    async fn list_dead_letters(&mut self, queue: &str, offset: isize, limit: isize) -> Result<Vec<DeadLetter>, QueueError>;
+
+    async fn dead_letter_count(&mut self, queue: &str) -> Result<usize, QueueError>;
+
+    /// Put one dead letter back on its queue with a fresh attempt count
+    /// Returns false if no dead letter has that id
+    async fn requeue_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError>;
+
+    /// Put every dead letter on a queue back, returning how many moved
+    async fn requeue_all_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError>;
+
+    /// Delete one dead letter by id
+    async fn purge_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError>;
+
+    /// Delete every dead letter on a queue, returning how many were removed
+    async fn purge_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError>;
+
+    /// Check if job already processed (idempotency)
+    async fn is_job_processed(&mut self, job_id: &str) -> Result<bool, QueueError>;
+
+    /// Mark job as processed for the next 24 hours (idempotency)
+    async fn mark_job_processed(&mut self, job_id: &str) -> Result<(), QueueError>;
+}
+
+impl<'a> dyn JobQueue + 'a {
+    /// Wrap a job in a versioned envelope and queue it on its job type's queue,
+    /// returning the envelope id
+    pub async fn push_job<J: QueueJob>(&mut self, job: &J) -> Result<String, QueueError> {
+        let envelope = JobEnvelope::new(job).map_err(QueueError::Job)?;
+        self.enqueue(J::QUEUE, &envelope).await?;
+
+        Ok(envelope.id)
+    }
+
+    /// Queue a job to run at `run_at`, returning the envelope id to cancel it by
+    pub async fn schedule_job<J: QueueJob>(&mut self, job: &J, run_at: DateTime<Utc>) -> Result<String, QueueError> {
+        let envelope = JobEnvelope::new(job).map_err(QueueError::Job)?;
+        self.enqueue_at(J::QUEUE, &envelope, run_at).await?;
+        println!("⏳ {} job {} scheduled for {}", J::JOB_TYPE, envelope.id, run_at);
+
+        Ok(envelope.id)
+    }
+
+    /// Queue a job to run after `delay`
+    pub async fn push_job_in<J: QueueJob>(&mut self, job: &J, delay: Duration) -> Result<String, QueueError> {
+        let run_at = Utc::now() + ChronoDuration::milliseconds(delay.as_millis() as i64);
+        self.schedule_job(job, run_at).await
+    }
+
+    /// Push payment confirmation job to queue
+    pub async fn push_confirmation_job(&mut self, job: &PaymentConfirmationJob) -> Result<(), QueueError> {
+        let id = self.push_job(job).await?;
+        println!("📤 Confirmation job pushed to queue: {} (payment {})", id, job.payment_id);
+
+        Ok(())
+    }
+
+    /// Reserve the next confirmation job (for worker)
+    pub async fn reserve_confirmation_job(&mut self, timeout_secs: f64) -> Result<Option<ReservedJob>, QueueError> {
+        self.reserve(CONFIRMATION_QUEUE, timeout_secs).await
+    }
+}
+
+/// Job queue for the configured backend (QUEUE_BACKEND)
+pub async fn connect(config: &Config) -> Result<Box<dyn JobQueue>, QueueError> {
+    let settings = QueueSettings::from_config(config);
+
+    let queue: Box<dyn JobQueue> = match config.queue_backend.as_str() {
+        "postgres" => Box::new(PgQueue::connect(&config.database_url).await?.with_settings(settings)),
+        _ => Box::new(QueueService::from_config(config).await?),
+    };
+
+    Ok(queue)
+}
+
+/// Visibility timeout and retry policies shared by every backend
+#[derive(Debug, Clone)]
+pub struct QueueSettings {
+    pub visibility_timeout_secs: u64,
+    /// Retry policy per queue (job type); queues not listed use the default
+    pub retry_policies: HashMap<String, RetryPolicy>,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        QueueSettings {
+            visibility_timeout_secs: DEFAULT_VISIBILITY_TIMEOUT_SECS,
+            retry_policies: HashMap::new(),
+        }
+    }
+}
+
+impl QueueSettings {
+    pub fn from_config(config: &Config) -> Self {
+        QueueSettings {
+            retry_policies: config.retry_policies.clone(),
+            ..QueueSettings::default()
+        }
+        .with_visibility_timeout(config.queue_visibility_timeout_secs)
+    }
+
+    /// How long reserved jobs stay invisible before reclaim puts them back
+    pub fn with_visibility_timeout(mut self, secs: u64) -> Self {
+        self.visibility_timeout_secs = secs.max(1);
+        self
+    }
+
+    /// Retry/backoff for jobs on one queue
+    pub fn with_retry_policy(mut self, queue: &str, policy: RetryPolicy) -> Self {
+        self.retry_policies.insert(queue.to_string(), policy);
+        self
+    }
+
+    pub fn retry_policy(&self, queue: &str) -> RetryPolicy {
+        self.retry_policies.get(queue).copied().unwrap_or_default()
+    }
+}
+
+/// Why a queue operation failed
+#[derive(Debug)]
+pub enum QueueError {
+    Redis(redis::RedisError),
+    Database(sqlx::Error),
+    /// Job rejected before it was queued
+    Job(JobError),
+    Serialization(String),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::Redis(e) => write!(f, "Redis error: {}", e),
+            QueueError::Database(e) => write!(f, "Database error: {}", e),
+            QueueError::Job(e) => write!(f, "Invalid job: {}", e),
+            QueueError::Serialization(e) => write!(f, "Serialization failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+impl From<redis::RedisError> for QueueError {
+    fn from(e: redis::RedisError) -> Self {
+        QueueError::Redis(e)
+    }
+}
+
+impl From<sqlx::Error> for QueueError {
+    fn from(e: sqlx::Error) -> Self {
+        QueueError::Database(e)
+    }
+}
+
+impl From<serde_json::Error> for QueueError {
+    fn from(e: serde_json::Error) -> Self {
+        QueueError::Serialization(e.to_string())
+    }
+}
+
+/// Queue depth and consumer lag snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueMetrics {
+    pub queue: String,
+    pub backend: &'static str,
+    /// Jobs waiting to be handed to a worker (stream consumer group lag)
+    pub ready: Option<u64>,
+    /// Reserved but not yet acked
+    pub in_flight: u64,
+    /// Waiting out a retry backoff or scheduled for later
+    pub delayed: u64,
+    pub dead: u64,
+    /// Consumers registered in the group (streams only)
+    pub consumers: Option<u64>,
+}
+
+/// Job reserved by a worker - stays in flight until acked
+#[derive(Debug, Clone)]
+pub struct ReservedJob {
+    pub queue: String,
+    pub id: String,
+    /// None when the queued entry isn't a job envelope
+    pub envelope: Option<JobEnvelope>,
+    /// Deliveries so far, including this one
+    pub attempts: i64,
+    /// Job exactly as queued, needed to ack or delay it on Redis lists
+    raw: String,
+    /// Backend handle for the reservation (stream entry id, jobs row id)
+    receipt: Option<String>,
+}
+
+impl ReservedJob {
+    /// Parse a queued entry, keying entries without an envelope by content
+    fn new(queue: &str, raw: String, attempts: i64, receipt: Option<String>) -> Self {
+        let envelope = serde_json::from_str::<JobEnvelope>(&raw).ok();
+        let id = job_id(envelope.as_ref(), &raw);
+
+        ReservedJob {
+            queue: queue.to_string(),
+            id,
+            envelope,
+            attempts,
+            raw,
+            receipt,
+        }
+    }
+
+    /// Typed payload - malformed, mistyped or unknown-version jobs are rejected
+    pub fn decode<J: QueueJob>(&self) -> Result<J, JobError> {
+        self.envelope.as_ref().ok_or(JobError::Unversioned)?.decode()
+    }
+
+    /// Payload as stored, for logging and dead letters
+    pub fn payload(&self) -> serde_json::Value {
+        match &self.envelope {
+            Some(envelope) => envelope.payload.clone(),
+            None => serde_json::from_str(&self.raw).unwrap_or_else(|_| serde_json::Value::String(self.raw.clone())),
+        }
+    }
+
+    /// Dead letter recording this job and the error that finished it
+    fn dead_letter(&self, error: &str) -> DeadLetter {
+        DeadLetter {
+            id: self.id.clone(),
+            queue: self.queue.clone(),
+            job_type: self.envelope.as_ref().map(|envelope| envelope.job_type.clone()),
+            version: self.envelope.as_ref().map(|envelope| envelope.version),
+            payload: self.payload(),
+            attempts: self.attempts,
+            last_error: error.to_string(),
+            failed_at: Utc::now(),
+        }
+    }
+}
+
+/// Envelope id, or a content hash for entries that aren't envelopes
+/// (queued by an older release or garbage)
+fn job_id(envelope: Option<&JobEnvelope>, raw: &str) -> String {
+    match envelope {
+        Some(envelope) => envelope.id.clone(),
+        None => hex::encode(Sha256::digest(raw.as_bytes())),
+    }
+}
+
+/// Job waiting for its run time
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub queue: String,
+    /// Envelope type, absent for entries that aren't envelopes
+    pub job_type: Option<String>,
+    pub run_at: DateTime<Utc>,
+}
+
+impl ScheduledJob {
+    fn new(queue: &str, raw: &str, run_at: DateTime<Utc>) -> Self {
+        let envelope = serde_json::from_str::<JobEnvelope>(raw).ok();
+
+        ScheduledJob {
+            id: job_id(envelope.as_ref(), raw),
+            queue: queue.to_string(),
+            job_type: envelope.map(|envelope| envelope.job_type),
+            run_at,
+        }
+    }
+}
+
+/// Job that exhausted its retries, with the error that finished it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: String,
+    pub queue: String,
+    /// Envelope type and version, absent for jobs that had no envelope
+    pub job_type: Option<String>,
+    pub version: Option<u32>,
+    pub payload: serde_json::Value,
+    pub attempts: i64,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl DeadLetter {
+    /// Job to queue again: same id, type and version, so the worker decides
+    /// again whether it can handle it
+    fn requeue_entry(self) -> Result<String, QueueError> {
+        let entry = match (self.job_type, self.version) {
+            (Some(job_type), Some(version)) => serde_json::to_string(&JobEnvelope {
+                id: self.id,
+                job_type,
+                version,
+                enqueued_at: Utc::now(),
+                payload: self.payload,
+            })?,
+            _ => serde_json::to_string(&self.payload)?,
+        };
+
+        Ok(entry)
+    }
+}
+
+/// Error recorded when a job is redelivered more often than its policy allows
+fn exhausted_error(max_attempts: u32, last_error: Option<String>) -> String {
+    let error = last_error.unwrap_or_else(|| "Visibility timeout expired (worker crashed or hung)".to_string());
+    format!("Exceeded {} attempts: {}", max_attempts, error)
+}
@@ -0,0 +1,418 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::{
+    exhausted_error, job_id, DeadLetter, JobQueue, QueueError, QueueMetrics, QueueSettings, ReservedJob, ScheduledJob,
+};
+use crate::database::Database;
+use crate::services::jobs::JobEnvelope;
+use crate::services::retry::RetryPolicy;
+
+/// How often an idle reserve checks the jobs table again
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Postgres queue: one `queue_jobs` row per job, claimed with FOR UPDATE SKIP LOCKED
+/// A reserved row's `run_at` is its lease deadline and a delayed row's `run_at` is
+/// its due time, so expired reservations and elapsed backoffs are simply claimable again
+#[derive(Clone)]
+pub struct PgQueue {
+    pool: PgPool,
+    settings: QueueSettings,
+}
+
+/// Dead-lettered row
+#[derive(FromRow)]
+struct DeadRow {
+    queue: String,
+    job_id: String,
+    payload: String,
+    attempts: i64,
+    last_error: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+impl DeadRow {
+    fn into_dead_letter(self) -> DeadLetter {
+        let envelope = serde_json::from_str::<JobEnvelope>(&self.payload).ok();
+
+        DeadLetter {
+            id: self.job_id,
+            queue: self.queue,
+            job_type: envelope.as_ref().map(|envelope| envelope.job_type.clone()),
+            version: envelope.as_ref().map(|envelope| envelope.version),
+            payload: match envelope {
+                Some(envelope) => envelope.payload,
+                None => serde_json::from_str(&self.payload).unwrap_or(serde_json::Value::String(self.payload)),
+            },
+            attempts: self.attempts,
+            last_error: self.last_error.unwrap_or_default(),
+            failed_at: self.updated_at,
+        }
+    }
+}
+
+impl PgQueue {
+    pub fn new(pool: PgPool) -> Self {
+        PgQueue {
+            pool,
+            settings: QueueSettings::default(),
+        }
+    }
+
+    /// Queue on its own connection pool
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        Ok(PgQueue::new(Database::new(database_url).await?.pool))
+    }
+
+    pub fn with_settings(mut self, settings: QueueSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Claim the oldest due job, leasing it for the visibility timeout
+    /// Returns the job and the last error recorded against it
+    async fn claim(&self, queue: &str) -> Result<Option<(ReservedJob, Option<String>)>, sqlx::Error> {
+        let row = sqlx::query_as::<_, (i64, String, i64, Option<String>)>(
+            r#"
+            UPDATE queue_jobs
+            SET status = 'reserved',
+                attempts = attempts + 1,
+                run_at = NOW() + make_interval(secs => $2),
+                updated_at = NOW()
+            WHERE seq = (
+                SELECT seq FROM queue_jobs
+                WHERE queue = $1 AND status IN ('ready', 'reserved') AND run_at <= NOW()
+                ORDER BY run_at, seq
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING seq, payload, attempts, last_error
+            "#,
+        )
+        .bind(queue)
+        .bind(self.settings.visibility_timeout_secs as f64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(seq, payload, attempts, last_error)| {
+            (ReservedJob::new(queue, payload, attempts, Some(seq.to_string())), last_error)
+        }))
+    }
+}
+
+/// Row a reservation refers to
+/// The attempt count guards against acking a job another worker has since reclaimed
+fn receipt(job: &ReservedJob) -> i64 {
+    job.receipt.as_deref().and_then(|seq| seq.parse().ok()).unwrap_or_default()
+}
+
+#[async_trait]
+impl JobQueue for PgQueue {
+    fn backend_name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn retry_policy(&self, queue: &str) -> RetryPolicy {
+        self.settings.retry_policy(queue)
+    }
+
+    fn clone_queue(&self) -> Box<dyn JobQueue> {
+        Box::new(self.clone())
+    }
+
+    async fn enqueue(&mut self, queue: &str, envelope: &JobEnvelope) -> Result<(), QueueError> {
+        self.enqueue_at(queue, envelope, Utc::now()).await
+    }
+
+    /// A ready row with a future `run_at` - claimable once it passes
+    async fn enqueue_at(&mut self, queue: &str, envelope: &JobEnvelope, run_at: DateTime<Utc>) -> Result<(), QueueError> {
+        let payload = serde_json::to_string(envelope)?;
+
+        sqlx::query("INSERT INTO queue_jobs (queue, job_id, payload, run_at) VALUES ($1, $2, $3, $4)")
+            .bind(queue)
+            .bind(job_id(Some(envelope), &payload))
+            .bind(&payload)
+            .bind(run_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_scheduled(&mut self, queue: &str, limit: isize) -> Result<Vec<ScheduledJob>, QueueError> {
+        let rows = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+            r#"
+            SELECT payload, run_at
+            FROM queue_jobs
+            WHERE queue = $1 AND status = 'ready' AND run_at > NOW()
+            ORDER BY run_at, seq
+            LIMIT $2
+            "#,
+        )
+        .bind(queue)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|(payload, run_at)| ScheduledJob::new(queue, payload, *run_at))
+            .collect())
+    }
+
+    async fn cancel_scheduled(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let result = sqlx::query(
+            "DELETE FROM queue_jobs WHERE queue = $1 AND job_id = $2 AND status = 'ready' AND run_at > NOW()",
+        )
+        .bind(queue)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Polls until a job is due or the timeout passes
+    async fn reserve(&mut self, queue: &str, timeout_secs: f64) -> Result<Option<ReservedJob>, QueueError> {
+        let deadline = Instant::now() + Duration::from_secs_f64(timeout_secs.max(0.0));
+
+        loop {
+            if let Some((job, last_error)) = self.claim(queue).await? {
+                // Redelivered after expired leases more often than the policy allows
+                let max_attempts = self.retry_policy(queue).max_attempts;
+                if job.attempts > max_attempts as i64 {
+                    self.dead_letter(&job, &exhausted_error(max_attempts, last_error)).await?;
+                    return Ok(None);
+                }
+
+                println!("📥 Job reserved from {}: {} (attempt {})", queue, job.id, job.attempts);
+                return Ok(Some(job));
+            }
+
+            if Instant::now() + POLL_INTERVAL > deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn ack(&mut self, job: &ReservedJob) -> Result<(), QueueError> {
+        sqlx::query("DELETE FROM queue_jobs WHERE seq = $1 AND attempts = $2 AND status = 'reserved'")
+            .bind(receipt(job))
+            .bind(job.attempts)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn retry_later(&mut self, job: &ReservedJob, error: &str) -> Result<bool, QueueError> {
+        let policy = self.retry_policy(&job.queue);
+
+        if job.attempts >= policy.max_attempts as i64 {
+            self.dead_letter(job, error).await?;
+            return Ok(true);
+        }
+
+        let delay = policy.backoff(job.attempts as u32);
+
+        sqlx::query(
+            r#"
+            UPDATE queue_jobs
+            SET status = 'ready',
+                run_at = NOW() + make_interval(secs => $3),
+                last_error = $4,
+                updated_at = NOW()
+            WHERE seq = $1 AND attempts = $2 AND status = 'reserved'
+            "#,
+        )
+        .bind(receipt(job))
+        .bind(job.attempts)
+        .bind(delay.as_secs_f64())
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        println!("🔁 Job {} retrying in {}s (attempt {}/{})", job.id, delay.as_secs(), job.attempts, policy.max_attempts);
+
+        Ok(false)
+    }
+
+    async fn dead_letter(&mut self, job: &ReservedJob, error: &str) -> Result<(), QueueError> {
+        sqlx::query(
+            r#"
+            UPDATE queue_jobs
+            SET status = 'dead', last_error = $3, updated_at = NOW()
+            WHERE seq = $1 AND attempts = $2 AND status = 'reserved'
+            "#,
+        )
+        .bind(receipt(job))
+        .bind(job.attempts)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        eprintln!("☠️  Job {} dead-lettered on {}: {}", job.id, job.queue, error);
+
+        Ok(())
+    }
+
+    /// Nothing to do - expired reservations are claimed again directly
+    async fn reclaim_stale_jobs(&mut self, _queue: &str) -> Result<usize, QueueError> {
+        Ok(0)
+    }
+
+    /// Nothing to do - delayed rows become claimable once `run_at` passes
+    async fn promote_delayed_jobs(&mut self, _queue: &str) -> Result<usize, QueueError> {
+        Ok(0)
+    }
+
+    async fn metrics(&mut self, queue: &str) -> Result<QueueMetrics, QueueError> {
+        let (ready, in_flight, delayed, dead) = sqlx::query_as::<_, (i64, i64, i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'ready' AND run_at <= NOW()),
+                COUNT(*) FILTER (WHERE status = 'reserved'),
+                COUNT(*) FILTER (WHERE status = 'ready' AND run_at > NOW()),
+                COUNT(*) FILTER (WHERE status = 'dead')
+            FROM queue_jobs
+            WHERE queue = $1
+            "#,
+        )
+        .bind(queue)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(QueueMetrics {
+            queue: queue.to_string(),
+            backend: self.backend_name(),
+            ready: Some(ready as u64),
+            in_flight: in_flight as u64,
+            delayed: delayed as u64,
+            dead: dead as u64,
+            consumers: None,
+        })
+    }
+
+    async fn list_dead_letters(&mut self, queue: &str, offset: isize, limit: isize) -> Result<Vec<DeadLetter>, QueueError> {
+        let rows = sqlx::query_as::<_, DeadRow>(
+            r#"
+            SELECT queue, job_id, payload, attempts, last_error, updated_at
+            FROM queue_jobs
+            WHERE queue = $1 AND status = 'dead'
+            ORDER BY updated_at DESC, seq DESC
+            OFFSET $2 LIMIT $3
+            "#,
+        )
+        .bind(queue)
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(DeadRow::into_dead_letter).collect())
+    }
+
+    async fn dead_letter_count(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let (count,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM queue_jobs WHERE queue = $1 AND status = 'dead'",
+        )
+        .bind(queue)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as usize)
+    }
+
+    async fn requeue_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE queue_jobs
+            SET status = 'ready', attempts = 0, run_at = NOW(), last_error = NULL, updated_at = NOW()
+            WHERE queue = $1 AND job_id = $2 AND status = 'dead'
+            "#,
+        )
+        .bind(queue)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            println!("📤 Dead letter {} requeued on {}", id, queue);
+        }
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn requeue_all_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE queue_jobs
+            SET status = 'ready', attempts = 0, run_at = NOW(), last_error = NULL, updated_at = NOW()
+            WHERE queue = $1 AND status = 'dead'
+            "#,
+        )
+        .bind(queue)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn purge_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let result = sqlx::query("DELETE FROM queue_jobs WHERE queue = $1 AND job_id = $2 AND status = 'dead'")
+            .bind(queue)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let result = sqlx::query("DELETE FROM queue_jobs WHERE queue = $1 AND status = 'dead'")
+            .bind(queue)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn is_job_processed(&mut self, job_id: &str) -> Result<bool, QueueError> {
+        let (exists,) = sqlx::query_as::<_, (bool,)>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM processed_jobs
+                WHERE job_id = $1 AND processed_at > NOW() - INTERVAL '24 hours'
+            )
+            "#,
+        )
+        .bind(job_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    async fn mark_job_processed(&mut self, job_id: &str) -> Result<(), QueueError> {
+        sqlx::query(
+            r#"
+            INSERT INTO processed_jobs (job_id, processed_at) VALUES ($1, NOW())
+            ON CONFLICT (job_id) DO UPDATE SET processed_at = NOW()
+            "#,
+        )
+        .bind(job_id)
+        .execute(&self.pool)
+        .await?;
+
+        // Same 24 hour window as the Redis TTL
+        sqlx::query("DELETE FROM processed_jobs WHERE processed_at < NOW() - INTERVAL '24 hours'")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
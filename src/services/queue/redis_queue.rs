@@ -0,0 +1,560 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::{aio::ConnectionManager, AsyncCommands, Client, Script};
+use std::collections::HashSet;
+
+use super::{
+    exhausted_error, job_id, DeadLetter, JobQueue, QueueError, QueueMetrics, QueueSettings, ReservedJob, ScheduledJob,
+};
+use crate::config::Config;
+use crate::services::events::{PaymentEvent, PAYMENT_EVENTS_CHANNEL};
+use crate::services::jobs::JobEnvelope;
+use crate::services::retry::RetryPolicy;
+
+/// Put an expired in-flight job back at the head of its queue
+/// Only the caller that removes it from the processing list requeues it
+const REQUEUE_SCRIPT: &str = r#"
+if redis.call('LREM', KEYS[2], 1, ARGV[1]) > 0 then
+    redis.call('RPUSH', KEYS[1], ARGV[1])
+end
+redis.call('ZREM', KEYS[3], ARGV[1])
+return 1
+"#;
+
+/// Move delayed jobs that are due back onto their queue
+const PROMOTE_SCRIPT: &str = r#"
+local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, 100)
+for _, job in ipairs(due) do
+    redis.call('ZREM', KEYS[1], job)
+    redis.call('RPUSH', KEYS[2], job)
+end
+return #due
+"#;
+
+/// Where reliable jobs live in Redis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueBackend {
+    /// LPUSH/BLMOVE lists with a processing list and leases (single consumer group)
+    List,
+    /// Redis Streams consumer group - many worker replicas share one stream,
+    /// each tracked by consumer name, with pending entries reclaimed via XAUTOCLAIM
+    Streams { group: String, consumer: String },
+}
+
+impl QueueBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueueBackend::List => "list",
+            QueueBackend::Streams { .. } => "streams",
+        }
+    }
+}
+
+/// Redis keys backing one reliable queue
+fn processing_key(queue: &str) -> String {
+    format!("{}:processing", queue)
+}
+
+fn leases_key(queue: &str) -> String {
+    format!("{}:leases", queue)
+}
+
+fn attempts_key(queue: &str) -> String {
+    format!("{}:attempts", queue)
+}
+
+/// Last error per job id, kept across retries
+fn errors_key(queue: &str) -> String {
+    format!("{}:errors", queue)
+}
+
+/// Jobs waiting out their retry backoff or scheduled for later (score = due time in ms)
+pub(super) fn delayed_key(queue: &str) -> String {
+    format!("{}:delayed", queue)
+}
+
+/// Stream carrying a queue's jobs on the streams backend
+pub(super) fn stream_key(queue: &str) -> String {
+    format!("{}:stream", queue)
+}
+
+/// Jobs that exhausted their retries
+fn dead_key(queue: &str) -> String {
+    format!("{}:dead", queue)
+}
+
+/// Redis queue manager
+/// Clones share the multiplexed connection for ordinary commands; blocking
+/// reads go over a connection each handle opens for itself, since Redis
+/// serves a connection's commands one at a time
+pub struct QueueService {
+    client: Client,
+    pub(super) connection: ConnectionManager,
+    /// Dedicated to BLMOVE / XREADGROUP BLOCK, opened on first reserve
+    blocking_connection: Option<ConnectionManager>,
+    pub(super) backend: QueueBackend,
+    /// Streams whose consumer group has been created by this process
+    pub(super) stream_groups: HashSet<String>,
+    pub(super) settings: QueueSettings,
+}
+
+impl QueueService {
+    /// Create new queue service
+    pub async fn new(redis_url: &str) -> Result<Self, redis::RedisError> {
+        let client = Client::open(redis_url)?;
+        let connection = ConnectionManager::new(client.clone()).await?;
+        
+        println!("✅ Redis connected successfully!");
+        
+        Ok(QueueService {
+            client,
+            connection,
+            blocking_connection: None,
+            backend: QueueBackend::List,
+            stream_groups: HashSet::new(),
+            settings: QueueSettings::default(),
+        })
+    }
+
+    /// Queue service set up from config: backend, visibility timeout and retry policies
+    pub async fn from_config(config: &Config) -> Result<Self, redis::RedisError> {
+        let backend = match config.queue_backend.as_str() {
+            "streams" => QueueBackend::Streams {
+                group: config.queue_consumer_group.clone(),
+                consumer: config.queue_consumer_name.clone(),
+            },
+            _ => QueueBackend::List,
+        };
+
+        Ok(QueueService::new(&config.redis_url)
+            .await?
+            .with_backend(backend)
+            .with_settings(QueueSettings::from_config(config)))
+    }
+
+    pub fn with_backend(mut self, backend: QueueBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn backend(&self) -> &QueueBackend {
+        &self.backend
+    }
+
+    pub fn with_settings(mut self, settings: QueueSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// This handle's own connection for blocking reads
+    pub(super) async fn blocking_connection(&mut self) -> Result<&mut ConnectionManager, redis::RedisError> {
+        let connection = match self.blocking_connection.take() {
+            Some(connection) => connection,
+            None => ConnectionManager::new(self.client.clone()).await?,
+        };
+
+        Ok(self.blocking_connection.insert(connection))
+    }
+
+    /// Put a serialized job where the backend's consumers read from
+    async fn enqueue_raw(&mut self, queue: &str, raw: &str) -> Result<(), redis::RedisError> {
+        match self.backend {
+            QueueBackend::List => self.connection.lpush::<_, _, ()>(queue, raw).await,
+            QueueBackend::Streams { .. } => self.stream_add(queue, raw).await,
+        }
+    }
+
+    /// Queue the commands that take a job off the in-flight set
+    fn release_in_flight(&self, pipe: &mut redis::Pipeline, job: &ReservedJob) {
+        let queue = job.queue.as_str();
+        match (&self.backend, &job.receipt) {
+            (QueueBackend::Streams { group, .. }, Some(stream_id)) => {
+                pipe.xack(stream_key(queue), group, &[stream_id])
+                    .ignore()
+                    .xdel(stream_key(queue), &[stream_id])
+                    .ignore();
+            }
+            _ => {
+                pipe.lrem(processing_key(queue), 1, &job.raw)
+                    .ignore()
+                    .zrem(leases_key(queue), &job.raw)
+                    .ignore();
+            }
+        }
+    }
+
+    async fn requeue_raw_dead_letter(&mut self, queue: &str, raw: &str, letter: DeadLetter) -> Result<bool, QueueError> {
+        let removed: i64 = self.connection.lrem(dead_key(queue), 1, raw).await?;
+        if removed == 0 {
+            // Someone else requeued or purged it first
+            return Ok(false);
+        }
+
+        let id = letter.id.clone();
+        let entry_json = letter.requeue_entry()?;
+        self.enqueue_raw(queue, &entry_json).await?;
+        println!("📤 Dead letter {} requeued on {}", id, queue);
+
+        Ok(true)
+    }
+
+    /// Publish a payment status change to every API instance
+    pub async fn publish_payment_event(&mut self, event: &PaymentEvent) -> Result<(), redis::RedisError> {
+        let event_json = serde_json::to_string(event)
+            .map_err(|e| redis::RedisError::from((redis::ErrorKind::TypeError, "Serialization failed", e.to_string())))?;
+
+        self.connection
+            .publish::<_, _, ()>(PAYMENT_EVENTS_CHANNEL, event_json)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl Clone for QueueService {
+    /// Another handle on the same queues - it opens its own blocking connection
+    fn clone(&self) -> Self {
+        QueueService {
+            client: self.client.clone(),
+            connection: self.connection.clone(),
+            blocking_connection: None,
+            backend: self.backend.clone(),
+            stream_groups: self.stream_groups.clone(),
+            settings: self.settings.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl JobQueue for QueueService {
+    fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    fn retry_policy(&self, queue: &str) -> RetryPolicy {
+        self.settings.retry_policy(queue)
+    }
+
+    fn clone_queue(&self) -> Box<dyn JobQueue> {
+        Box::new(self.clone())
+    }
+
+    async fn enqueue(&mut self, queue: &str, envelope: &JobEnvelope) -> Result<(), QueueError> {
+        let job_json = serde_json::to_string(envelope)?;
+        self.enqueue_raw(queue, &job_json).await?;
+
+        Ok(())
+    }
+
+    async fn enqueue_at(&mut self, queue: &str, envelope: &JobEnvelope, run_at: DateTime<Utc>) -> Result<(), QueueError> {
+        if run_at <= Utc::now() {
+            return self.enqueue(queue, envelope).await;
+        }
+
+        // Same set as retry backoffs, so the promoter picks it up when due
+        let job_json = serde_json::to_string(envelope)?;
+        let _: () = self.connection
+            .zadd(delayed_key(queue), &job_json, run_at.timestamp_millis())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_scheduled(&mut self, queue: &str, limit: isize) -> Result<Vec<ScheduledJob>, QueueError> {
+        let entries: Vec<(String, i64)> = self.connection
+            .zrange_withscores(delayed_key(queue), 0, limit - 1)
+            .await?;
+
+        Ok(entries
+            .iter()
+            .map(|(raw, due)| ScheduledJob::new(queue, raw, DateTime::from_timestamp_millis(*due).unwrap_or_default()))
+            .collect())
+    }
+
+    async fn cancel_scheduled(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let waiting: Vec<String> = self.connection.zrange(delayed_key(queue), 0, -1).await?;
+
+        for raw in waiting {
+            if job_id(serde_json::from_str(&raw).ok().as_ref(), &raw) == id {
+                // Zero if the promoter moved it to the queue first
+                let removed: i64 = self.connection.zrem(delayed_key(queue), &raw).await?;
+                return Ok(removed > 0);
+            }
+        }
+
+        Ok(false)
+    }
+
+    async fn reserve(&mut self, queue: &str, timeout_secs: f64) -> Result<Option<ReservedJob>, QueueError> {
+        let (raw, stream_id) = match self.backend {
+            QueueBackend::List => {
+                // BLMOVE: the job is never only in this process's memory
+                let raw: Option<String> = self
+                    .blocking_connection()
+                    .await?
+                    .blmove(queue, processing_key(queue), redis::Direction::Right, redis::Direction::Left, timeout_secs)
+                    .await?;
+
+                let Some(raw) = raw else {
+                    return Ok(None);
+                };
+
+                let deadline = Utc::now().timestamp_millis() + (self.settings.visibility_timeout_secs * 1000) as i64;
+                let _: () = self.connection.zadd(leases_key(queue), &raw, deadline).await?;
+
+                (raw, None)
+            }
+            QueueBackend::Streams { .. } => match self.stream_read(queue, timeout_secs).await? {
+                Some((stream_id, raw)) => (raw, Some(stream_id)),
+                None => return Ok(None),
+            },
+        };
+
+        let mut job = ReservedJob::new(queue, raw, 0, stream_id);
+        job.attempts = self.connection.hincr(attempts_key(queue), &job.id, 1).await?;
+
+        // Redelivered by reclaim more often than the policy allows
+        let max_attempts = self.retry_policy(queue).max_attempts;
+        if job.attempts > max_attempts as i64 {
+            let last_error: Option<String> = self.connection.hget(errors_key(queue), &job.id).await?;
+            self.dead_letter(&job, &exhausted_error(max_attempts, last_error)).await?;
+            return Ok(None);
+        }
+
+        println!("📥 Job reserved from {}: {} (attempt {})", queue, job.id, job.attempts);
+
+        Ok(Some(job))
+    }
+
+    async fn ack(&mut self, job: &ReservedJob) -> Result<(), QueueError> {
+        let queue = job.queue.as_str();
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        self.release_in_flight(&mut pipe, job);
+        let _: () = pipe
+            .hdel(attempts_key(queue), &job.id)
+            .ignore()
+            .hdel(errors_key(queue), &job.id)
+            .ignore()
+            .query_async(&mut self.connection)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn retry_later(&mut self, job: &ReservedJob, error: &str) -> Result<bool, QueueError> {
+        let policy = self.retry_policy(&job.queue);
+
+        if job.attempts >= policy.max_attempts as i64 {
+            self.dead_letter(job, error).await?;
+            return Ok(true);
+        }
+
+        let delay = policy.backoff(job.attempts as u32);
+        let due = Utc::now().timestamp_millis() + delay.as_millis() as i64;
+        let queue = job.queue.as_str();
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        self.release_in_flight(&mut pipe, job);
+        let _: () = pipe
+            .zadd(delayed_key(queue), &job.raw, due)
+            .ignore()
+            .hset(errors_key(queue), &job.id, error)
+            .ignore()
+            .query_async(&mut self.connection)
+            .await?;
+
+        println!("🔁 Job {} retrying in {}s (attempt {}/{})", job.id, delay.as_secs(), job.attempts, policy.max_attempts);
+
+        Ok(false)
+    }
+
+    async fn dead_letter(&mut self, job: &ReservedJob, error: &str) -> Result<(), QueueError> {
+        let queue = job.queue.as_str();
+        let letter_json = serde_json::to_string(&job.dead_letter(error))?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        self.release_in_flight(&mut pipe, job);
+        let _: () = pipe
+            .hdel(attempts_key(queue), &job.id)
+            .ignore()
+            .hdel(errors_key(queue), &job.id)
+            .ignore()
+            .lpush(dead_key(queue), letter_json)
+            .ignore()
+            .query_async(&mut self.connection)
+            .await?;
+
+        eprintln!("☠️  Job {} dead-lettered on {}: {}", job.id, queue, error);
+
+        Ok(())
+    }
+
+    /// Jobs with no lease yet get one, so a worker between BLMOVE and ZADD isn't robbed
+    /// Streams reclaim pending entries with XAUTOCLAIM on every reserve instead
+    async fn reclaim_stale_jobs(&mut self, queue: &str) -> Result<usize, QueueError> {
+        if let QueueBackend::Streams { .. } = self.backend {
+            return Ok(0);
+        }
+
+        let in_flight: Vec<String> = self.connection.lrange(processing_key(queue), 0, -1).await?;
+        let now = Utc::now().timestamp_millis();
+        let script = Script::new(REQUEUE_SCRIPT);
+        let mut reclaimed = 0;
+
+        for raw in in_flight {
+            let lease: Option<f64> = self.connection.zscore(leases_key(queue), &raw).await?;
+
+            match lease {
+                Some(deadline) if deadline <= now as f64 => {
+                    let _: i64 = script
+                        .key(queue)
+                        .key(processing_key(queue))
+                        .key(leases_key(queue))
+                        .arg(&raw)
+                        .invoke_async(&mut self.connection)
+                        .await?;
+                    reclaimed += 1;
+                }
+                Some(_) => {}
+                None => {
+                    let deadline = now + (self.settings.visibility_timeout_secs * 1000) as i64;
+                    let _: () = redis::cmd("ZADD")
+                        .arg(leases_key(queue))
+                        .arg("NX")
+                        .arg(deadline)
+                        .arg(&raw)
+                        .query_async(&mut self.connection)
+                        .await?;
+                }
+            }
+        }
+
+        if reclaimed > 0 {
+            println!("♻️  Reclaimed {} stale job(s) on {}", reclaimed, queue);
+        }
+
+        Ok(reclaimed)
+    }
+
+    async fn promote_delayed_jobs(&mut self, queue: &str) -> Result<usize, QueueError> {
+        if let QueueBackend::Streams { .. } = self.backend {
+            return Ok(self.stream_promote_delayed(queue).await?);
+        }
+
+        let promoted: usize = Script::new(PROMOTE_SCRIPT)
+            .key(delayed_key(queue))
+            .key(queue)
+            .arg(Utc::now().timestamp_millis())
+            .invoke_async(&mut self.connection)
+            .await?;
+
+        Ok(promoted)
+    }
+
+    async fn metrics(&mut self, queue: &str) -> Result<QueueMetrics, QueueError> {
+        let delayed: u64 = self.connection.zcard(delayed_key(queue)).await?;
+        let dead: u64 = self.connection.llen(dead_key(queue)).await?;
+
+        let mut metrics = match self.backend {
+            QueueBackend::List => QueueMetrics {
+                queue: queue.to_string(),
+                backend: self.backend.name(),
+                ready: Some(self.connection.llen(queue).await?),
+                in_flight: self.connection.llen(processing_key(queue)).await?,
+                delayed: 0,
+                dead: 0,
+                consumers: None,
+            },
+            QueueBackend::Streams { .. } => self.stream_metrics(queue).await?,
+        };
+        metrics.delayed = delayed;
+        metrics.dead = dead;
+
+        Ok(metrics)
+    }
+
+    async fn list_dead_letters(&mut self, queue: &str, offset: isize, limit: isize) -> Result<Vec<DeadLetter>, QueueError> {
+        let raw: Vec<String> = self.connection
+            .lrange(dead_key(queue), offset, offset + limit - 1)
+            .await?;
+
+        Ok(raw
+            .iter()
+            .filter_map(|letter| serde_json::from_str(letter).ok())
+            .collect())
+    }
+
+    async fn dead_letter_count(&mut self, queue: &str) -> Result<usize, QueueError> {
+        Ok(self.connection.llen(dead_key(queue)).await?)
+    }
+
+    async fn requeue_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let letters: Vec<String> = self.connection.lrange(dead_key(queue), 0, -1).await?;
+
+        for raw in letters {
+            let Ok(letter) = serde_json::from_str::<DeadLetter>(&raw) else {
+                continue;
+            };
+            if letter.id == id {
+                return self.requeue_raw_dead_letter(queue, &raw, letter).await;
+            }
+        }
+
+        Ok(false)
+    }
+
+    async fn requeue_all_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let letters: Vec<String> = self.connection.lrange(dead_key(queue), 0, -1).await?;
+        let mut requeued = 0;
+
+        for raw in letters {
+            let Ok(letter) = serde_json::from_str::<DeadLetter>(&raw) else {
+                continue;
+            };
+            if self.requeue_raw_dead_letter(queue, &raw, letter).await? {
+                requeued += 1;
+            }
+        }
+
+        Ok(requeued)
+    }
+
+    async fn purge_dead_letter(&mut self, queue: &str, id: &str) -> Result<bool, QueueError> {
+        let letters: Vec<String> = self.connection.lrange(dead_key(queue), 0, -1).await?;
+
+        for raw in letters {
+            if serde_json::from_str::<DeadLetter>(&raw).is_ok_and(|letter| letter.id == id) {
+                let removed: i64 = self.connection.lrem(dead_key(queue), 1, &raw).await?;
+                return Ok(removed > 0);
+            }
+        }
+
+        Ok(false)
+    }
+
+    async fn purge_dead_letters(&mut self, queue: &str) -> Result<usize, QueueError> {
+        let count = self.dead_letter_count(queue).await?;
+        self.connection.del::<_, ()>(dead_key(queue)).await?;
+
+        Ok(count)
+    }
+
+    async fn is_job_processed(&mut self, job_id: &str) -> Result<bool, QueueError> {
+        let exists: bool = self.connection
+            .exists(format!("processed:{}", job_id))
+            .await?;
+        
+        Ok(exists)
+    }
+
+    async fn mark_job_processed(&mut self, job_id: &str) -> Result<(), QueueError> {
+        let _: () = self.connection
+            .set_ex(format!("processed:{}", job_id), "1", 86400) // 24 hours TTL
+            .await?;
+        
+        Ok(())
+    }
+}
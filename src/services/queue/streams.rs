@@ -0,0 +1,168 @@
+use chrono::Utc;
+use redis::streams::{StreamClaimReply, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Script, Value};
+use std::collections::HashMap;
+
+use super::redis_queue::{delayed_key, stream_key, QueueBackend, QueueService};
+use super::QueueMetrics;
+
+/// Consumer group shared by all worker replicas unless configured otherwise
+pub const DEFAULT_CONSUMER_GROUP: &str = "payment-workers";
+
+/// Stream entry field holding the job envelope
+const JOB_FIELD: &str = "job";
+
+/// Move delayed jobs that are due onto the stream
+const PROMOTE_STREAM_SCRIPT: &str = r#"
+local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, 100)
+for _, job in ipairs(due) do
+    redis.call('ZREM', KEYS[1], job)
+    redis.call('XADD', KEYS[2], '*', 'job', job)
+end
+return #due
+"#;
+
+/// Redis Streams backend: XADD / XREADGROUP / XACK / XAUTOCLAIM
+impl QueueService {
+    pub(super) async fn stream_add(&mut self, queue: &str, raw: &str) -> Result<(), redis::RedisError> {
+        self.connection
+            .xadd::<_, _, _, _, ()>(stream_key(queue), "*", &[(JOB_FIELD, raw)])
+            .await
+    }
+
+    /// Next job for this consumer: a stale pending entry abandoned by another
+    /// consumer if there is one, otherwise a new entry (blocking up to the timeout)
+    pub(super) async fn stream_read(
+        &mut self,
+        queue: &str,
+        timeout_secs: f64,
+    ) -> Result<Option<(String, String)>, redis::RedisError> {
+        let QueueBackend::Streams { group, consumer } = self.backend.clone() else {
+            return Ok(None);
+        };
+        self.ensure_group(queue, &group).await?;
+
+        if let Some(entry) = self.stream_autoclaim(queue, &group, &consumer).await? {
+            return Ok(Some(entry));
+        }
+
+        let options = StreamReadOptions::default()
+            .group(&group, &consumer)
+            .count(1)
+            .block((timeout_secs * 1000.0) as usize);
+
+        let reply: Option<StreamReadReply> = self
+            .blocking_connection()
+            .await?
+            .xread_options(&[stream_key(queue)], &[">"], &options)
+            .await?;
+
+        let entry = reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .next();
+
+        Ok(entry.map(|entry| {
+            let raw = entry.get::<String>(JOB_FIELD).unwrap_or_default();
+            (entry.id, raw)
+        }))
+    }
+
+    /// Claim one entry that has been pending longer than the visibility timeout
+    async fn stream_autoclaim(
+        &mut self,
+        queue: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<Option<(String, String)>, redis::RedisError> {
+        let reply: Vec<Value> = redis::cmd("XAUTOCLAIM")
+            .arg(stream_key(queue))
+            .arg(group)
+            .arg(consumer)
+            .arg(self.settings.visibility_timeout_secs * 1000)
+            .arg("0-0")
+            .arg("COUNT")
+            .arg(1)
+            .query_async(&mut self.connection)
+            .await?;
+
+        let Some(entries) = reply.get(1) else {
+            return Ok(None);
+        };
+        let claimed: StreamClaimReply = redis::from_redis_value(entries)?;
+
+        Ok(claimed.ids.into_iter().next().map(|entry| {
+            println!("♻️  Claimed stale stream entry {} on {}", entry.id, queue);
+            let raw = entry.get::<String>(JOB_FIELD).unwrap_or_default();
+            (entry.id, raw)
+        }))
+    }
+
+    /// Create the consumer group (and stream) once per process
+    /// Starts at the beginning so jobs queued before any worker ran are delivered
+    async fn ensure_group(&mut self, queue: &str, group: &str) -> Result<(), redis::RedisError> {
+        if self.stream_groups.contains(queue) {
+            return Ok(());
+        }
+
+        let created: Result<(), redis::RedisError> = self.connection
+            .xgroup_create_mkstream(stream_key(queue), group, "0")
+            .await;
+
+        match created {
+            Ok(()) => println!("📡 Created consumer group {} on {}", group, stream_key(queue)),
+            Err(e) if e.code() == Some("BUSYGROUP") => {}
+            Err(e) => return Err(e),
+        }
+
+        self.stream_groups.insert(queue.to_string());
+        Ok(())
+    }
+
+    pub(super) async fn stream_promote_delayed(&mut self, queue: &str) -> Result<usize, redis::RedisError> {
+        Script::new(PROMOTE_STREAM_SCRIPT)
+            .key(delayed_key(queue))
+            .key(stream_key(queue))
+            .arg(Utc::now().timestamp_millis())
+            .invoke_async(&mut self.connection)
+            .await
+    }
+
+    /// Lag (entries not yet delivered to the group), pending entries and consumers
+    pub(super) async fn stream_metrics(&mut self, queue: &str) -> Result<QueueMetrics, redis::RedisError> {
+        let QueueBackend::Streams { group, .. } = self.backend.clone() else {
+            return Err(redis::RedisError::from((redis::ErrorKind::ClientError, "Not a streams queue")));
+        };
+        self.ensure_group(queue, &group).await?;
+
+        let groups: Vec<HashMap<String, Value>> = redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(stream_key(queue))
+            .query_async(&mut self.connection)
+            .await?;
+
+        let info = groups.into_iter().find(|info| {
+            info.get("name")
+                .and_then(|name| redis::from_redis_value::<String>(name).ok())
+                .is_some_and(|name| name == group)
+        });
+
+        let field = |name: &str| -> Option<u64> {
+            info.as_ref()?
+                .get(name)
+                .and_then(|value| redis::from_redis_value::<u64>(value).ok())
+        };
+
+        Ok(QueueMetrics {
+            queue: queue.to_string(),
+            backend: self.backend.name(),
+            // `lag` needs Redis 7; it is nil when Redis can't compute it
+            ready: field("lag"),
+            in_flight: field("pending").unwrap_or(0),
+            delayed: 0,
+            dead: 0,
+            consumers: field("consumers"),
+        })
+    }
+}
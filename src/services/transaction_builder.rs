@@ -0,0 +1,294 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use solana_system_interface::{instruction as system_instruction, program as system_program};
+use std::str::FromStr;
+
+use crate::config::tokens::TokenInfo;
+
+/// Associated Token Account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Source of recent blockhashes for new transactions
+/// Pluggable so transaction building can run without an RPC node
+pub trait BlockhashSource: Send + Sync {
+    fn latest_blockhash(&self) -> Result<Hash, String>;
+}
+
+/// Fetch blockhashes from a Solana RPC node
+pub struct RpcBlockhashSource {
+    client: RpcClient,
+}
+
+impl RpcBlockhashSource {
+    pub fn new(rpc_url: &str) -> Self {
+        RpcBlockhashSource {
+            client: RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()),
+        }
+    }
+}
+
+impl BlockhashSource for RpcBlockhashSource {
+    fn latest_blockhash(&self) -> Result<Hash, String> {
+        self.client
+            .get_latest_blockhash()
+            .map_err(|e| format!("Failed to fetch blockhash: {}", e))
+    }
+}
+
+/// Always returns the same blockhash (offline use and tests)
+pub struct FixedBlockhashSource(pub Hash);
+
+impl BlockhashSource for FixedBlockhashSource {
+    fn latest_blockhash(&self) -> Result<Hash, String> {
+        Ok(self.0)
+    }
+}
+
+/// Everything needed to build a payment transaction for a customer
+#[derive(Debug, Clone)]
+pub struct PaymentTransactionParams<'a> {
+    /// Customer wallet (signs the transfer)
+    pub payer: Pubkey,
+    /// Pays the network fee instead of the customer (fee sponsorship)
+    pub fee_payer: Option<Pubkey>,
+    /// Merchant wallet
+    pub recipient: Pubkey,
+    /// Amount in token base units
+    pub amount: u64,
+    pub token: &'a TokenInfo,
+    /// Solana Pay reference key, added as a read-only account
+    pub reference: Option<Pubkey>,
+    pub memo: &'a str,
+}
+
+/// Build the unsigned payment transaction:
+/// memo instruction followed by a SOL transfer, or for tokens an idempotent
+/// create of the merchant's token account and `transfer_checked`
+pub fn build_payment_transaction(
+    params: &PaymentTransactionParams,
+    recent_blockhash: Hash,
+) -> Result<Transaction, String> {
+    let mut instructions: Vec<Instruction> = vec![spl_memo::build_memo(params.memo.as_bytes(), &[])];
+
+    let mut transfer_ix = match params.token.mint {
+        None => system_instruction::transfer(&params.payer, &params.recipient, params.amount),
+        Some(mint) => {
+            let mint = Pubkey::from_str(mint)
+                .map_err(|e| format!("Invalid mint for {}: {}", params.token.symbol, e))?;
+            let source = associated_token_address(&params.payer, &mint);
+            let destination = associated_token_address(&params.recipient, &mint);
+
+            // The merchant may not hold the token yet - the customer funds the
+            // account rent, so a sponsor only ever pays the network fee
+            instructions.push(create_associated_token_account_idempotent(
+                &params.payer,
+                &params.recipient,
+                &mint,
+            ));
+
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                &source,
+                &mint,
+                &destination,
+                &params.payer,
+                &[],
+                params.amount,
+                params.token.decimals,
+            )
+            .map_err(|e| format!("Failed to build token transfer: {}", e))?
+        }
+    };
+
+    // Reference lets the merchant find the transaction with getSignaturesForAddress
+    if let Some(reference) = params.reference {
+        transfer_ix.accounts.push(AccountMeta::new_readonly(reference, false));
+    }
+
+    instructions.push(transfer_ix);
+    let fee_payer = params.fee_payer.unwrap_or(params.payer);
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&fee_payer));
+    transaction.message.recent_blockhash = recent_blockhash;
+
+    Ok(transaction)
+}
+
+/// Serialize a (possibly partially signed) transaction as base64 wire format
+pub fn encode_transaction(transaction: &Transaction) -> Result<String, String> {
+    let bytes = bincode::serialize(transaction)
+        .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+    Ok(STANDARD.encode(bytes))
+}
+
+/// Derive the associated token account for a wallet and mint
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), spl_token::id().as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Create `wallet`'s associated token account unless it already exists
+/// (`CreateIdempotent`, instruction 1 of the ATA program)
+pub fn create_associated_token_account_idempotent(
+    funding: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*funding, true),
+            AccountMeta::new(associated_token_address(wallet, mint), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: vec![1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tokens::find_token;
+
+    fn params<'a>(token: &'a TokenInfo) -> PaymentTransactionParams<'a> {
+        PaymentTransactionParams {
+            payer: Pubkey::new_unique(),
+            fee_payer: None,
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            token,
+            reference: Some(Pubkey::new_unique()),
+            memo: "order-42",
+        }
+    }
+
+    /// Program ids of the transaction's instructions, in order
+    fn programs(transaction: &Transaction) -> Vec<Pubkey> {
+        let message = &transaction.message;
+        message
+            .instructions
+            .iter()
+            .map(|ix| message.account_keys[ix.program_id_index as usize])
+            .collect()
+    }
+
+    fn build(params: &PaymentTransactionParams) -> Transaction {
+        let blockhash = FixedBlockhashSource(Hash::new_unique()).latest_blockhash().unwrap();
+        build_payment_transaction(params, blockhash).unwrap()
+    }
+
+    #[test]
+    fn fixed_source_returns_its_blockhash() {
+        let hash = Hash::new_unique();
+        let params = params(find_token("SOL").unwrap());
+
+        let transaction = build_payment_transaction(&params, FixedBlockhashSource(hash).latest_blockhash().unwrap()).unwrap();
+
+        assert_eq!(transaction.message.recent_blockhash, hash);
+    }
+
+    #[test]
+    fn sol_payment_is_memo_then_system_transfer() {
+        let params = params(find_token("SOL").unwrap());
+        let transaction = build(&params);
+
+        assert_eq!(programs(&transaction), vec![spl_memo::id(), system_program::id()]);
+        assert_eq!(transaction.message.account_keys[0], params.payer);
+        assert_eq!(transaction.message.header.num_required_signatures, 1);
+
+        let memo_ix = &transaction.message.instructions[0];
+        assert_eq!(memo_ix.data, b"order-42");
+
+        let keys = &transaction.message.account_keys;
+        assert!(keys.contains(&params.recipient));
+        assert!(keys.contains(&params.reference.unwrap()));
+    }
+
+    #[test]
+    fn token_payment_uses_transfer_checked_between_atas() {
+        let token = find_token("USDC").unwrap();
+        let params = params(token);
+        let transaction = build(&params);
+
+        assert_eq!(
+            programs(&transaction),
+            vec![spl_memo::id(), ASSOCIATED_TOKEN_PROGRAM_ID, spl_token::id()]
+        );
+
+        let mint = Pubkey::from_str(token.mint.unwrap()).unwrap();
+        let keys = &transaction.message.account_keys;
+        assert!(keys.contains(&associated_token_address(&params.payer, &mint)));
+        assert!(keys.contains(&associated_token_address(&params.recipient, &mint)));
+        assert!(keys.contains(&mint));
+    }
+
+    #[test]
+    fn token_payment_creates_merchant_ata_funded_by_customer() {
+        let token = find_token("USDC").unwrap();
+        let mut params = params(token);
+        params.fee_payer = Some(Pubkey::new_unique());
+        let transaction = build(&params);
+
+        let message = &transaction.message;
+        let create_ix = &message.instructions[1];
+        let accounts: Vec<Pubkey> = create_ix
+            .accounts
+            .iter()
+            .map(|&index| message.account_keys[index as usize])
+            .collect();
+
+        let mint = Pubkey::from_str(token.mint.unwrap()).unwrap();
+        assert_eq!(create_ix.data, vec![1]);
+        assert_eq!(accounts[0], params.payer);
+        assert_eq!(accounts[1], associated_token_address(&params.recipient, &mint));
+        assert_eq!(accounts[2], params.recipient);
+        assert_eq!(accounts[3], mint);
+        // Still only the sponsor and the customer sign
+        assert_eq!(message.header.num_required_signatures, 2);
+    }
+
+    #[test]
+    fn sponsored_fee_payer_signs_first() {
+        let sponsor = Pubkey::new_unique();
+        let mut params = params(find_token("SOL").unwrap());
+        params.fee_payer = Some(sponsor);
+
+        let transaction = build(&params);
+
+        assert_eq!(transaction.message.account_keys[0], sponsor);
+        assert_eq!(transaction.message.account_keys[1], params.payer);
+        assert_eq!(transaction.message.header.num_required_signatures, 2);
+    }
+
+    #[test]
+    fn invalid_mint_is_an_error() {
+        let token = TokenInfo { mint: Some("not-a-mint"), ..*find_token("USDC").unwrap() };
+
+        let error = build_payment_transaction(&params(&token), Hash::new_unique()).unwrap_err();
+
+        assert!(error.starts_with("Invalid mint for USDC"));
+    }
+
+    #[test]
+    fn encoded_transaction_round_trips() {
+        let transaction = build(&params(find_token("SOL").unwrap()));
+
+        let bytes = STANDARD.decode(encode_transaction(&transaction).unwrap()).unwrap();
+        let decoded: Transaction = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(decoded, transaction);
+    }
+}
@@ -0,0 +1,458 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::database::models::{PaymentRequest, PaymentStatusResponse};
+
+/// Event types delivered to webhook endpoints
+pub const EVENT_PAYMENT_CREATED: &str = "payment.created";
+pub const EVENT_PAYMENT_CONFIRMED: &str = "payment.confirmed";
+pub const EVENT_PAYMENT_UNDERPAID: &str = "payment.underpaid";
+pub const EVENT_PAYMENT_EXPIRED: &str = "payment.expired";
+
+pub const EVENT_TYPES: &[&str] = &[
+    EVENT_PAYMENT_CREATED,
+    EVENT_PAYMENT_CONFIRMED,
+    EVENT_PAYMENT_UNDERPAID,
+    EVENT_PAYMENT_EXPIRED,
+];
+
+/// Headers sent with every delivery
+pub const HEADER_EVENT_ID: &str = "X-Webhook-Id";
+pub const HEADER_EVENT_TYPE: &str = "X-Webhook-Event";
+pub const HEADER_TIMESTAMP: &str = "X-Webhook-Timestamp";
+pub const HEADER_SIGNATURE: &str = "X-Webhook-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Registered webhook endpoint (matches webhook_endpoints table)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub merchant_address: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Empty means every event type
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Secret replaced by the last rotation
+    #[serde(skip_serializing)]
+    pub previous_secret: Option<String>,
+    /// Deliveries are also signed with the previous secret until this time
+    pub previous_secret_expires_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookEndpoint {
+    pub fn subscribes_to(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+
+    /// Signature header value: one v1 per secret currently accepted
+    /// During a rotation overlap receivers can verify with either secret
+    pub fn signature_header(&self, timestamp: i64, body: &str) -> String {
+        let mut header = format!("t={},v1={}", timestamp, sign_payload(&self.secret, timestamp, body));
+
+        if let (Some(previous), Some(expires_at)) = (&self.previous_secret, self.previous_secret_expires_at)
+            && expires_at > Utc::now()
+        {
+            header.push_str(&format!(",v1={}", sign_payload(previous, timestamp, body)));
+        }
+
+        header
+    }
+}
+
+/// Event payload as delivered to merchants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub created_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+impl WebhookEvent {
+    pub fn new(event_type: &str, data: Value) -> Self {
+        WebhookEvent {
+            id: Uuid::new_v4(),
+            event_type: event_type.to_string(),
+            created_at: Utc::now(),
+            data,
+        }
+    }
+}
+
+/// Event carrying a payment's current state as `data`
+pub fn payment_webhook_event(event_type: &str, payment: &PaymentRequest) -> WebhookEvent {
+    let data = serde_json::to_value(PaymentStatusResponse::from(payment.clone()))
+        .unwrap_or(Value::Null);
+    WebhookEvent::new(event_type, data)
+}
+
+/// Logged delivery attempt (matches webhook_deliveries table)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    #[sqlx(json)]
+    pub payload: WebhookEvent,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub latency_ms: i64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Result of a single delivery attempt
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryAttempt {
+    pub status_code: Option<u16>,
+    pub latency_ms: i64,
+    pub error: Option<String>,
+}
+
+impl DeliveryAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.status_code.is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+/// HMAC-SHA256 over "{timestamp}.{body}", hex encoded
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Check a signature header ("t=<ts>,v1=<hex>[,v1=<hex>]") against a body
+/// Rejects timestamps further than `tolerance_secs` from now (replay protection)
+pub fn verify_signature(secret: &str, header: &str, body: &str, tolerance_secs: i64) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if (Utc::now().timestamp() - timestamp).abs() > tolerance_secs {
+        return false;
+    }
+
+    signatures.into_iter().any(|signature| {
+        let Ok(expected) = hex::decode(signature) else {
+            return false;
+        };
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body.as_bytes());
+        // Constant-time comparison
+        mac.verify_slice(&expected).is_ok()
+    })
+}
+
+/// Generate a new endpoint signing secret
+pub fn generate_secret() -> String {
+    format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Whether an address is on the public internet
+/// Webhooks must not reach the gateway's own network: loopback, private,
+/// link-local, shared (CGNAT) and other special-purpose ranges are refused
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0 && ip.octets()[2] == 0)
+                || (a == 198 && (18..20).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+        }
+    }
+}
+
+/// Check that a webhook URL's host resolves only to public addresses
+pub async fn check_public_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let host = parsed.host_str().ok_or("URL has no host")?;
+    let port = parsed.port_or_known_default().unwrap_or(443);
+
+    // IPv6 literals keep their brackets in host_str
+    if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        return match is_public_ip(ip) {
+            true => Ok(()),
+            false => Err(format!("{} is not a public address", ip)),
+        };
+    }
+
+    let host = host.to_ascii_lowercase();
+    if host == "localhost" || host.ends_with(".localhost") {
+        return Err(format!("{} is not a public host", host));
+    }
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .collect();
+
+    match addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        _ if addrs.is_empty() => Err(format!("{} did not resolve", host)),
+        Some(addr) => Err(format!("{} resolves to {}, which is not a public address", host, addr.ip())),
+        None => Ok(()),
+    }
+}
+
+/// Resolver for deliveries that drops non-public addresses, so a hostname
+/// re-pointed at an internal address after it was checked still can't reach it
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Sends signed webhook events and records every attempt in webhook_deliveries
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    pool: PgPool,
+    client: reqwest::Client,
+    /// Skip the public address checks (local development)
+    allow_private_hosts: bool,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: PgPool, timeout: Duration, allow_private_hosts: bool) -> Result<Self, reqwest::Error> {
+        // Redirects aren't followed - they could point anywhere
+        let mut builder = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("payment-gateway-rust-webhooks")
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private_hosts {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+
+        Ok(WebhookDispatcher {
+            pool,
+            client: builder.build()?,
+            allow_private_hosts,
+        })
+    }
+
+    /// Refuse URLs that point into the gateway's own network
+    /// Checked when an endpoint is registered and again before every delivery
+    pub async fn check_url(&self, url: &str) -> Result<(), String> {
+        if self.allow_private_hosts {
+            return Ok(());
+        }
+        check_public_url(url).await
+    }
+
+    /// Endpoint by id, active or not
+    pub async fn endpoint(&self, id: Uuid) -> Result<Option<WebhookEndpoint>, sqlx::Error> {
+        sqlx::query_as::<_, WebhookEndpoint>("SELECT * FROM webhook_endpoints WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Single signed POST, recorded in webhook_deliveries
+    pub async fn deliver(&self, endpoint: &WebhookEndpoint, event: &WebhookEvent, attempt: i32) -> DeliveryAttempt {
+        let body = match serde_json::to_string(event) {
+            Ok(body) => body,
+            Err(e) => {
+                return DeliveryAttempt {
+                    status_code: None,
+                    latency_ms: 0,
+                    error: Some(format!("Serialization failed: {}", e)),
+                }
+            }
+        };
+
+        let result = match self.check_url(&endpoint.url).await {
+            Ok(()) => self.send(endpoint, event, body).await,
+            Err(e) => DeliveryAttempt {
+                status_code: None,
+                latency_ms: 0,
+                error: Some(format!("Blocked: {}", e)),
+            },
+        };
+
+        if let Err(e) = self.record_delivery(endpoint, event, attempt, &result).await {
+            eprintln!("⚠️  Failed to record webhook delivery: {}", e);
+        }
+
+        result
+    }
+
+    /// POST the signed body to the endpoint
+    async fn send(&self, endpoint: &WebhookEndpoint, event: &WebhookEvent, body: String) -> DeliveryAttempt {
+        let timestamp = Utc::now().timestamp();
+        let signature = endpoint.signature_header(timestamp, &body);
+
+        let started = Instant::now();
+        let response = self
+            .client
+            .post(&endpoint.url)
+            .header("Content-Type", "application/json")
+            .header(HEADER_EVENT_ID, event.id.to_string())
+            .header(HEADER_EVENT_TYPE, &event.event_type)
+            .header(HEADER_TIMESTAMP, timestamp.to_string())
+            .header(HEADER_SIGNATURE, signature)
+            .body(body)
+            .send()
+            .await;
+        let latency_ms = started.elapsed().as_millis() as i64;
+
+        match response {
+            Ok(response) => {
+                let status = response.status();
+                DeliveryAttempt {
+                    status_code: Some(status.as_u16()),
+                    latency_ms,
+                    error: (!status.is_success()).then(|| format!("HTTP {}", status)),
+                }
+            }
+            Err(e) => DeliveryAttempt {
+                status_code: None,
+                latency_ms,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// Attempt number for the next delivery of an event to an endpoint
+    pub async fn next_attempt(&self, endpoint_id: Uuid, event_id: Uuid) -> Result<i32, sqlx::Error> {
+        let (attempt,): (i32,) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(MAX(attempt), 0) + 1 FROM webhook_deliveries
+            WHERE endpoint_id = $1 AND event_id = $2
+            "#,
+        )
+        .bind(endpoint_id)
+        .bind(event_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(attempt)
+    }
+
+    async fn record_delivery(
+        &self,
+        endpoint: &WebhookEndpoint,
+        event: &WebhookEvent,
+        attempt: i32,
+        result: &DeliveryAttempt,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+            (id, endpoint_id, event_id, event_type, payload, attempt, status_code, latency_ms, success, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(endpoint.id)
+        .bind(event.id)
+        .bind(&event.event_type)
+        .bind(sqlx::types::Json(event))
+        .bind(attempt)
+        .bind(result.status_code.map(|code| code as i32))
+        .bind(result.latency_ms)
+        .bind(result.succeeded())
+        .bind(&result.error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{} should be refused", ip);
+        }
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn refuses_internal_webhook_urls_without_resolving() {
+        assert!(check_public_url("http://169.254.169.254/latest/meta-data").await.is_err());
+        assert!(check_public_url("https://[::1]:8080/hook").await.is_err());
+        assert!(check_public_url("https://api.localhost/hook").await.is_err());
+        assert!(check_public_url("https://93.184.216.34/hook").await.is_ok());
+    }
+}
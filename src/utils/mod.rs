@@ -1 +1,4 @@
+pub mod qr;
+pub mod shutdown;
+pub mod solana_pay;
 pub mod wallet;
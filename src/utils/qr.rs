@@ -0,0 +1,170 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use qrcode::{Color, EcLevel, QrCode};
+use std::io::Cursor;
+
+pub const DEFAULT_SIZE: u32 = 300;
+pub const MIN_SIZE: u32 = 64;
+pub const MAX_SIZE: u32 = 2048;
+pub const DEFAULT_MARGIN: u32 = 4;
+pub const MAX_MARGIN: u32 = 16;
+
+/// Logo side length as a fraction of the code width
+/// Error correction level H tolerates ~30% damage, so 22% leaves headroom
+const LOGO_SCALE: f32 = 0.22;
+
+/// QR rendering options
+#[derive(Debug, Clone)]
+pub struct QrOptions<'a> {
+    /// Target image width/height in pixels
+    pub size: u32,
+    /// Quiet zone in modules
+    pub margin: u32,
+    /// PNG logo drawn over the center of the code
+    pub logo: Option<&'a [u8]>,
+}
+
+impl Default for QrOptions<'_> {
+    fn default() -> Self {
+        QrOptions {
+            size: DEFAULT_SIZE,
+            margin: DEFAULT_MARGIN,
+            logo: None,
+        }
+    }
+}
+
+/// Encoded QR matrix plus layout derived from the options
+struct QrLayout {
+    modules: Vec<Color>,
+    width: u32,
+    module_px: u32,
+    margin: u32,
+}
+
+impl QrLayout {
+    fn new(data: &str, options: &QrOptions) -> Result<Self, String> {
+        // Higher error correction when part of the code is covered by a logo
+        let ec_level = if options.logo.is_some() { EcLevel::H } else { EcLevel::M };
+        let code = QrCode::with_error_correction_level(data.as_bytes(), ec_level)
+            .map_err(|e| format!("Failed to encode QR code: {}", e))?;
+
+        let width = code.width() as u32;
+        let margin = options.margin.min(MAX_MARGIN);
+        let size = options.size.clamp(MIN_SIZE, MAX_SIZE);
+        let module_px = (size / (width + 2 * margin)).max(1);
+
+        Ok(QrLayout {
+            modules: code.to_colors(),
+            width,
+            module_px,
+            margin,
+        })
+    }
+
+    /// Full image side length in pixels
+    fn image_px(&self) -> u32 {
+        (self.width + 2 * self.margin) * self.module_px
+    }
+
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        self.modules[(y * self.width + x) as usize] == Color::Dark
+    }
+}
+
+/// Render `data` as an SVG document
+pub fn render_svg(data: &str, options: &QrOptions) -> Result<String, String> {
+    let layout = QrLayout::new(data, options)?;
+    let total = layout.width + 2 * layout.margin;
+
+    // One path in module units, scaled by the viewBox
+    let mut path = String::new();
+    for y in 0..layout.width {
+        for x in 0..layout.width {
+            if layout.is_dark(x, y) {
+                path.push_str(&format!(
+                    "M{},{}h1v1h-1z",
+                    x + layout.margin,
+                    y + layout.margin
+                ));
+            }
+        }
+    }
+
+    let mut svg = format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{px}" height="{px}" "#,
+            r#"viewBox="0 0 {total} {total}" shape-rendering="crispEdges">"#,
+            r##"<rect width="{total}" height="{total}" fill="#ffffff"/>"##,
+            r##"<path fill="#000000" d="{path}"/>"##
+        ),
+        px = layout.image_px(),
+        total = total,
+        path = path
+    );
+
+    if let Some(logo) = options.logo {
+        let logo_size = layout.width as f32 * LOGO_SCALE;
+        let offset = total as f32 / 2.0 - logo_size / 2.0;
+        svg.push_str(&format!(
+            concat!(
+                r##"<rect x="{o}" y="{o}" width="{s}" height="{s}" fill="#ffffff"/>"##,
+                r#"<image x="{o}" y="{o}" width="{s}" height="{s}" href="data:image/png;base64,{data}"/>"#
+            ),
+            o = offset,
+            s = logo_size,
+            data = STANDARD.encode(logo)
+        ));
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+/// Render `data` as a PNG image
+pub fn render_png(data: &str, options: &QrOptions) -> Result<Vec<u8>, String> {
+    let layout = QrLayout::new(data, options)?;
+    let px = layout.image_px();
+
+    let white = Rgba([255, 255, 255, 255]);
+    let black = Rgba([0, 0, 0, 255]);
+
+    let mut canvas = RgbaImage::from_pixel(px, px, white);
+    for y in 0..layout.width {
+        for x in 0..layout.width {
+            if !layout.is_dark(x, y) {
+                continue;
+            }
+            let left = (x + layout.margin) * layout.module_px;
+            let top = (y + layout.margin) * layout.module_px;
+            for dy in 0..layout.module_px {
+                for dx in 0..layout.module_px {
+                    canvas.put_pixel(left + dx, top + dy, black);
+                }
+            }
+        }
+    }
+
+    if let Some(logo) = options.logo {
+        let logo = image::load_from_memory(logo)
+            .map_err(|e| format!("Failed to load logo: {}", e))?
+            .to_rgba8();
+
+        let logo_px = ((layout.width * layout.module_px) as f32 * LOGO_SCALE) as u32;
+        if logo_px > 0 {
+            let logo = imageops::resize(&logo, logo_px, logo_px, imageops::FilterType::Triangle);
+            let offset = (px - logo_px) / 2;
+            let backdrop = RgbaImage::from_pixel(logo_px, logo_px, white);
+            imageops::overlay(&mut canvas, &backdrop, offset as i64, offset as i64);
+            imageops::overlay(&mut canvas, &logo, offset as i64, offset as i64);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    canvas
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+
+    Ok(bytes)
+}
@@ -0,0 +1,146 @@
+use solana_sdk::signature::{Keypair, Signer};
+
+/// Solana Pay transfer request
+/// Spec: https://docs.solanapay.com/spec#specification-transfer-request
+#[derive(Debug, Clone, Default)]
+pub struct TransferRequest {
+    pub recipient: String,
+    /// Amount in base units (lamports / token base units)
+    pub amount: Option<u64>,
+    /// Decimals used to render `amount` (9 for SOL)
+    pub decimals: u8,
+    /// SPL token mint (None for native SOL)
+    pub spl_token: Option<String>,
+    pub references: Vec<String>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+    pub memo: Option<String>,
+}
+
+impl TransferRequest {
+    /// Build the `solana:` URL
+    pub fn to_url(&self) -> String {
+        let mut params: Vec<(&str, String)> = Vec::new();
+
+        if let Some(amount) = self.amount {
+            params.push(("amount", format_amount(amount, self.decimals)));
+        }
+        if let Some(ref mint) = self.spl_token {
+            params.push(("spl-token", mint.clone()));
+        }
+        for reference in &self.references {
+            params.push(("reference", reference.clone()));
+        }
+        if let Some(ref label) = self.label {
+            params.push(("label", label.clone()));
+        }
+        if let Some(ref message) = self.message {
+            params.push(("message", message.clone()));
+        }
+        if let Some(ref memo) = self.memo {
+            params.push(("memo", memo.clone()));
+        }
+
+        let query: Vec<String> = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+            .collect();
+
+        if query.is_empty() {
+            format!("solana:{}", self.recipient)
+        } else {
+            format!("solana:{}?{}", self.recipient, query.join("&"))
+        }
+    }
+}
+
+/// Render base units as a decimal string without float rounding
+/// e.g. (10_000_000, 9) -> "0.01", (1_500_000, 6) -> "1.5", (2_000_000_000, 9) -> "2"
+pub fn format_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let scale = 10u128.pow(decimals as u32);
+    let whole = amount as u128 / scale;
+    let fraction = amount as u128 % scale;
+
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+/// Percent-encode a query value (RFC 3986 unreserved characters pass through)
+pub fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Generate a unique reference key for a payment
+/// Only the public key is used - it is added to the transfer as a read-only account
+pub fn generate_reference() -> String {
+    Keypair::new().pubkey().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_amount_trims_without_rounding() {
+        assert_eq!(format_amount(10_000_000, 9), "0.01");
+        assert_eq!(format_amount(1_500_000, 6), "1.5");
+        assert_eq!(format_amount(2_000_000_000, 9), "2");
+        assert_eq!(format_amount(1, 9), "0.000000001");
+        assert_eq!(format_amount(0, 9), "0");
+        assert_eq!(format_amount(123, 0), "123");
+        assert_eq!(format_amount(u64::MAX, 9), "18446744073.709551615");
+    }
+
+    #[test]
+    fn bare_url_is_just_the_recipient() {
+        let request = TransferRequest {
+            recipient: "mvines9iiHiQTysrwkJjGf2gb9Ex9jXJX8ns3qwf2kN".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(request.to_url(), "solana:mvines9iiHiQTysrwkJjGf2gb9Ex9jXJX8ns3qwf2kN");
+    }
+
+    #[test]
+    fn url_orders_and_encodes_params() {
+        let request = TransferRequest {
+            recipient: "merchant".to_string(),
+            amount: Some(1_500_000),
+            decimals: 6,
+            spl_token: Some("mint".to_string()),
+            references: vec!["ref1".to_string(), "ref2".to_string()],
+            label: Some("Coffee & Co".to_string()),
+            message: Some("Thanks!".to_string()),
+            memo: Some("ORDER#1".to_string()),
+        };
+
+        assert_eq!(
+            request.to_url(),
+            "solana:merchant?amount=1.5&spl-token=mint&reference=ref1&reference=ref2\
+             &label=Coffee%20%26%20Co&message=Thanks%21&memo=ORDER%231"
+        );
+    }
+
+    #[test]
+    fn percent_encode_passes_unreserved_and_encodes_utf8() {
+        assert_eq!(percent_encode("a-Z_0.~"), "a-Z_0.~");
+        assert_eq!(percent_encode("café"), "caf%C3%A9");
+    }
+}
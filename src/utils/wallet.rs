@@ -28,7 +28,7 @@ pub fn load_wallet(path: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
     
     let json = fs::read_to_string(path)?;
     let bytes: Vec<u8> = serde_json::from_str(&json)?;
-    let keypair = Keypair::from_bytes(&bytes)?;
+    let keypair = Keypair::try_from(bytes.as_slice())?;
     
     println!("✅ Wallet loaded from: {}", path);
     Ok(keypair)
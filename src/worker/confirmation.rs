@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::{publish_event, JobFailure, JobHandler};
+use crate::database::confirmations::ConfirmOutcome;
+use crate::database::payments::PaymentRepository;
+use crate::services::jobs::{PaymentConfirmationJob, PaymentExpiryJob, QueueJob};
+use crate::services::queue::{JobQueue, QueueService};
+
+/// Settles pending payments from confirmation jobs queued by the indexer
+pub struct ConfirmationHandler {
+    pub payments: Arc<dyn PaymentRepository>,
+    pub events: Option<QueueService>,
+}
+
+#[async_trait]
+impl JobHandler for ConfirmationHandler {
+    type Job = PaymentConfirmationJob;
+
+    async fn handle(&self, job: PaymentConfirmationJob, queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        println!("💳 Payment ID: {}", job.payment_id);
+        println!("📝 Memo: {}", job.memo);
+        println!("👤 Sender: {}", job.sender_address);
+        println!("🔗 Signature: {}", job.tx_sig);
+        println!("💰 Amount: {} {} base units", job.amount_lamports, job.token_symbol);
+        if let Some(pt) = job.paid_at {
+            println!("⏰ Paid at: {}", pt);
+        }
+
+        // Settle the payment - only a pending row changes, exactly once
+        match self.payments.confirm(&job).await {
+            // Webhooks and the receipt email were queued with the status change
+            Ok(ConfirmOutcome::Updated { payment, .. }) => {
+                if payment.status == "underpaid" {
+                    println!("⚠️  Payment underpaid: received {} of {} base units", job.amount_lamports, payment.amount_lamports);
+                    println!("   Status: pending → underpaid");
+                } else {
+                    println!("✅ Payment confirmed successfully!");
+                    println!("   Status: pending → confirmed");
+                }
+
+                publish_event(&self.events, &payment).await;
+
+                // The expiry job would find nothing to do - drop it rather than let it wait
+                let expiry_id = PaymentExpiryJob::job_id(payment.id);
+                if let Err(e) = queue.cancel_scheduled(PaymentExpiryJob::QUEUE, &expiry_id).await {
+                    eprintln!("⚠️  Failed to cancel expiry job for payment {}: {}", payment.id, e);
+                }
+
+                Ok(())
+            }
+            Ok(ConfirmOutcome::AlreadyConfirmed(payment)) => {
+                // Redelivered job - the first delivery already did the work
+                println!("ℹ️  Payment {} already {} by this signature, nothing to do", payment.id, payment.status);
+                Ok(())
+            }
+            Ok(ConfirmOutcome::Conflict(conflict)) => {
+                eprintln!("🚫 Confirmation refused for payment {}: {}", conflict.payment_id, conflict.reason);
+                eprintln!("   Logged as conflict {}", conflict.id);
+                Ok(())
+            }
+            Ok(ConfirmOutcome::NotFound) => Err(JobFailure::Fatal(format!("Payment {} not found", job.payment_id))),
+            Err(e) => Err(JobFailure::Retry(format!("Failed to update payment: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use uuid::Uuid;
+
+    use crate::database::models::PaymentRequest;
+    use crate::database::payments::{MemoryPaymentRepository, NewPayment};
+    use crate::services::queue::MemoryQueue;
+
+    fn handler(payments: &MemoryPaymentRepository) -> ConfirmationHandler {
+        ConfirmationHandler {
+            payments: Arc::new(payments.clone()),
+            events: None,
+        }
+    }
+
+    async fn create(payments: &MemoryPaymentRepository, queue: &mut MemoryQueue) -> PaymentRequest {
+        let now = Utc::now();
+        let (payment, _) = payments
+            .create(NewPayment {
+                id: Uuid::new_v4(),
+                amount_lamports: 1_000,
+                token_symbol: "SOL".to_string(),
+                memo: format!("memo-{}", Uuid::new_v4()),
+                receiver_address: Pubkey::new_unique().to_string(),
+                created_at: now,
+                expires_at: now + Duration::minutes(15),
+                order_id: None,
+                order_id_unique: false,
+                customer_email: None,
+                reference: Pubkey::new_unique().to_string(),
+            })
+            .await
+            .unwrap();
+
+        // What the outbox relay does with the expiry job the repository scheduled
+        for (envelope, run_at) in payments.scheduled_jobs() {
+            queue.enqueue_at(PaymentExpiryJob::QUEUE, &envelope, run_at).await.unwrap();
+        }
+
+        payment
+    }
+
+    fn job(payment: &PaymentRequest) -> PaymentConfirmationJob {
+        PaymentConfirmationJob {
+            job_id: Uuid::new_v4().to_string(),
+            payment_id: payment.id,
+            memo: payment.memo.clone(),
+            sender_address: Pubkey::new_unique().to_string(),
+            tx_sig: Signature::new_unique().to_string(),
+            token_symbol: "SOL".to_string(),
+            amount_lamports: payment.amount_lamports,
+            fee_payer: Pubkey::new_unique().to_string(),
+            fee_lamports: 5000,
+            paid_at: Some(Utc::now()),
+        }
+    }
+
+    #[tokio::test]
+    async fn confirmation_cancels_expiry_job() {
+        let payments = MemoryPaymentRepository::new();
+        let mut queue = MemoryQueue::new();
+        let payment = create(&payments, &mut queue).await;
+        assert_eq!(queue.list_scheduled(PaymentExpiryJob::QUEUE, 10).await.unwrap().len(), 1);
+
+        handler(&payments).handle(job(&payment), &mut queue).await.unwrap();
+
+        assert_eq!(payments.get(payment.id).await.unwrap().unwrap().status, "confirmed");
+        assert!(queue.list_scheduled(PaymentExpiryJob::QUEUE, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn redelivered_job_is_a_no_op() {
+        let payments = MemoryPaymentRepository::new();
+        let mut queue = MemoryQueue::new();
+        let payment = create(&payments, &mut queue).await;
+        let handler = handler(&payments);
+        let job = job(&payment);
+
+        handler.handle(job.clone(), &mut queue).await.unwrap();
+        handler.handle(job, &mut queue).await.unwrap();
+
+        assert_eq!(payments.events().len(), 2);
+        assert!(payments.conflicts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn conflicting_signature_is_logged_not_retried() {
+        let payments = MemoryPaymentRepository::new();
+        let mut queue = MemoryQueue::new();
+        let payment = create(&payments, &mut queue).await;
+        let handler = handler(&payments);
+        let first = job(&payment);
+
+        handler.handle(first.clone(), &mut queue).await.unwrap();
+        handler.handle(job(&payment), &mut queue).await.unwrap();
+
+        let conflicts = payments.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].existing_tx_sig.as_deref(), Some(first.tx_sig.as_str()));
+        assert_eq!(payments.get(payment.id).await.unwrap().unwrap().tx_sig, Some(first.tx_sig));
+    }
+
+    #[tokio::test]
+    async fn unknown_payment_is_fatal() {
+        let payments = MemoryPaymentRepository::new();
+        let mut queue = MemoryQueue::new();
+        let payment = create(&payments, &mut queue).await;
+        let mut job = job(&payment);
+        job.payment_id = Uuid::new_v4();
+
+        let result = handler(&payments).handle(job, &mut queue).await;
+
+        assert!(matches!(result, Err(JobFailure::Fatal(_))));
+    }
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::{JobFailure, JobHandler};
+use crate::database::payments::PaymentRepository;
+use crate::services::email::ReceiptMailer;
+use crate::services::jobs::EmailReceiptJob;
+use crate::services::queue::JobQueue;
+
+/// Emails receipts for settled payments
+pub struct EmailReceiptHandler {
+    pub payments: Arc<dyn PaymentRepository>,
+    pub mailer: ReceiptMailer,
+}
+
+#[async_trait]
+impl JobHandler for EmailReceiptHandler {
+    type Job = EmailReceiptJob;
+
+    async fn handle(&self, job: EmailReceiptJob, _queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        let payment = self
+            .payments
+            .get(job.payment_id)
+            .await
+            .map_err(|e| JobFailure::Retry(format!("Failed to load payment: {}", e)))?
+            .ok_or_else(|| JobFailure::Fatal(format!("Payment {} not found", job.payment_id)))?;
+
+        self.mailer
+            .send_receipt(&payment)
+            .await
+            .map_err(|e| JobFailure::Retry(format!("Failed to send receipt: {}", e)))
+    }
+}
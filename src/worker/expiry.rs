@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::{publish_event, JobFailure, JobHandler};
+use crate::database::payments::PaymentRepository;
+use crate::services::jobs::{ExpirySweepJob, PaymentExpiryJob};
+use crate::services::queue::{JobQueue, QueueService};
+
+/// Expires one payment when its scheduled expiry job comes due
+/// Nothing to do if the payment was settled first
+pub struct PaymentExpiryHandler {
+    pub payments: Arc<dyn PaymentRepository>,
+    pub events: Option<QueueService>,
+}
+
+#[async_trait]
+impl JobHandler for PaymentExpiryHandler {
+    type Job = PaymentExpiryJob;
+
+    async fn handle(&self, job: PaymentExpiryJob, _queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        let expired = self
+            .payments
+            .expire(job.payment_id)
+            .await
+            .map_err(|e| JobFailure::Retry(format!("Failed to expire payment {}: {}", job.payment_id, e)))?;
+
+        // The payment.expired webhooks were queued with the status change
+        if let Some((payment, _)) = expired {
+            println!("⌛ Payment expired: {}", payment.id);
+
+            publish_event(&self.events, &payment).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Expires pending payments past their expiry time
+/// Catches payments whose expiry job never ran (e.g. created before expiry jobs existed)
+pub struct ExpirySweepHandler {
+    pub payments: Arc<dyn PaymentRepository>,
+    pub events: Option<QueueService>,
+}
+
+#[async_trait]
+impl JobHandler for ExpirySweepHandler {
+    type Job = ExpirySweepJob;
+
+    async fn handle(&self, _job: ExpirySweepJob, _queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        let expired = self
+            .payments
+            .expire_overdue()
+            .await
+            .map_err(|e| JobFailure::Retry(format!("Expiry sweep failed: {}", e)))?;
+
+        for (payment, _) in expired {
+            println!("⌛ Payment expired: {}", payment.id);
+
+            publish_event(&self.events, &payment).await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+pub mod confirmation;
+pub mod email;
+pub mod expiry;
+pub mod reconciliation;
+pub mod registry;
+pub mod webhook;
+
+pub use confirmation::ConfirmationHandler;
+pub use email::EmailReceiptHandler;
+pub use expiry::{ExpirySweepHandler, PaymentExpiryHandler};
+pub use reconciliation::ReconciliationHandler;
+pub use registry::{HandlerRegistry, JobFailure, JobHandler};
+pub use webhook::WebhookDeliveryHandler;
+
+use crate::database::models::PaymentRequest;
+use crate::services::events::PaymentEvent;
+use crate::services::queue::QueueService;
+
+/// Push a payment status change to live SSE/WebSocket clients
+/// `events` is None when Redis isn't available
+pub async fn publish_event(events: &Option<QueueService>, payment: &PaymentRequest) {
+    let Some(events) = events else {
+        return;
+    };
+
+    if let Err(e) = events.clone().publish_payment_event(&PaymentEvent::from(payment)).await {
+        eprintln!("⚠️  Failed to publish payment event: {}", e);
+    }
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::{JobFailure, JobHandler};
+use crate::database::fees::settle_failed_sponsored_fee;
+use crate::database::outbox::{is_signature_processed, record_matched_payment};
+use crate::database::payments::PaymentRepository;
+use crate::database::Database;
+use crate::indexer::{failed_transaction_fee, parse_transaction, payment_to_confirmation_job, SolanaIndexer};
+use crate::services::jobs::{JobEnvelope, ReconciliationJob};
+use crate::services::queue::{JobQueue, CONFIRMATION_QUEUE};
+
+/// Re-checks the wallet's recent transactions for payments the indexer missed
+/// Matches go through the outbox, exactly like the indexer's own
+pub struct ReconciliationHandler {
+    /// Outbox and processed signatures
+    pub db: Database,
+    pub payments: Arc<dyn PaymentRepository>,
+    pub indexer: Arc<SolanaIndexer>,
+    pub wallet_address: String,
+}
+
+#[async_trait]
+impl JobHandler for ReconciliationHandler {
+    type Job = ReconciliationJob;
+
+    async fn handle(&self, job: ReconciliationJob, _queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        // The RPC client blocks - keep it off the async workers
+        let indexer = self.indexer.clone();
+        let signatures = tokio::task::spawn_blocking(move || {
+            indexer.get_recent_signatures(job.signature_limit).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| JobFailure::Retry(e.to_string()))?
+        .map_err(|e| JobFailure::Retry(format!("Failed to fetch signatures: {}", e)))?;
+
+        let mut recovered = 0;
+        for signature in signatures {
+            match is_signature_processed(&self.db.pool, &signature).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => return Err(JobFailure::Retry(format!("Failed to check signature {}: {}", signature, e))),
+            }
+
+            if self.reconcile_signature(&signature).await? {
+                recovered += 1;
+            }
+        }
+
+        if recovered > 0 {
+            println!("🔁 Reconciliation recovered {} missed payment(s)", recovered);
+        }
+
+        Ok(())
+    }
+}
+
+impl ReconciliationHandler {
+    /// Queue a confirmation for one unprocessed signature if it pays a pending request
+    async fn reconcile_signature(&self, signature: &str) -> Result<bool, JobFailure> {
+        let indexer = self.indexer.clone();
+        let sig = signature.to_string();
+        let tx = tokio::task::spawn_blocking(move || indexer.get_transaction(&sig).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| JobFailure::Retry(e.to_string()))?;
+
+        let tx = match tx {
+            Ok(Some(tx)) => tx,
+            // Not visible yet, or the RPC node hiccuped - the next sweep tries again
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                eprintln!("⚠️  Reconciliation couldn't fetch {}: {}", signature, e);
+                return Ok(false);
+            }
+        };
+
+        // A failed sponsored transaction still cost our fee payer its fee
+        if let Some(fee) = failed_transaction_fee(&tx) {
+            settle_failed_sponsored_fee(&self.db.pool, signature, fee)
+                .await
+                .map_err(|e| JobFailure::Retry(format!("Failed to settle fee of {}: {}", signature, e)))?;
+        }
+
+        let Some(payment) = parse_transaction(&tx, signature, &self.wallet_address) else {
+            return Ok(false);
+        };
+        let Some(memo) = payment.memo.clone() else {
+            return Ok(false);
+        };
+
+        // Any status - a second payment for a settled memo still goes to
+        // the confirmation path, which logs it as a conflict
+        let matched = self
+            .payments
+            .find_by_memo(&self.wallet_address, &memo)
+            .await
+            .map_err(|e| JobFailure::Retry(format!("Failed to look up memo {}: {}", memo, e)))?;
+        let Some(payment_id) = matched.map(|matched| matched.id) else {
+            return Ok(false);
+        };
+
+        let Some(job) = payment_to_confirmation_job(payment, payment_id) else {
+            return Ok(false);
+        };
+        let envelope = match JobEnvelope::new(&job) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                eprintln!("❌ Invalid confirmation job for {}: {}", signature, e);
+                return Ok(false);
+            }
+        };
+
+        match record_matched_payment(&self.db.pool, signature, payment_id, CONFIRMATION_QUEUE, &envelope).await {
+            Ok(recorded) => {
+                if recorded {
+                    println!("✅ Reconciled payment {} from {}", payment_id, signature);
+                }
+                Ok(recorded)
+            }
+            Err(e) => Err(JobFailure::Retry(format!("Failed to record confirmation: {}", e))),
+        }
+    }
+}
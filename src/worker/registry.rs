@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::services::jobs::{JobError, QueueJob};
+use crate::services::queue::{JobQueue, ReservedJob};
+
+/// Why a handler couldn't finish a job
+#[derive(Debug, Clone)]
+pub enum JobFailure {
+    /// Might work later (database or network trouble) - retried with backoff
+    Retry(String),
+    /// Will never work - dead-lettered straight away
+    Fatal(String),
+}
+
+impl fmt::Display for JobFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobFailure::Retry(e) | JobFailure::Fatal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Handles every job of one type
+#[async_trait]
+pub trait JobHandler: Send + Sync + 'static {
+    type Job: QueueJob + Send + 'static;
+
+    /// `queue` is for follow-up jobs (webhook deliveries, receipts)
+    async fn handle(&self, job: Self::Job, queue: &mut dyn JobQueue) -> Result<(), JobFailure>;
+}
+
+/// Type-erased handler: decodes the envelope, then runs the typed handler
+#[async_trait]
+trait ErasedHandler: Send + Sync {
+    async fn run(&self, job: &ReservedJob, queue: &mut dyn JobQueue) -> Result<(), JobFailure>;
+}
+
+struct Typed<H>(H);
+
+#[async_trait]
+impl<H: JobHandler> ErasedHandler for Typed<H> {
+    async fn run(&self, reserved: &ReservedJob, queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        // Malformed or unknown-version jobs can't be fixed by retrying
+        let job: H::Job = reserved.decode().map_err(|e| JobFailure::Fatal(e.to_string()))?;
+        self.0.handle(job, queue).await
+    }
+}
+
+struct Registration {
+    queue: &'static str,
+    handler: Box<dyn ErasedHandler>,
+}
+
+/// Job handlers keyed by job type
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: BTreeMap<&'static str, Registration>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        HandlerRegistry::default()
+    }
+
+    /// Handle the handler's job type, replacing any earlier handler for it
+    pub fn register<H: JobHandler>(mut self, handler: H) -> Self {
+        self.handlers.insert(
+            <H::Job as QueueJob>::JOB_TYPE,
+            Registration {
+                queue: <H::Job as QueueJob>::QUEUE,
+                handler: Box::new(Typed(handler)),
+            },
+        );
+        self
+    }
+
+    /// Keep only the listed job types (all of them if the list is empty)
+    /// Fails if a listed type has no registered handler
+    pub fn only(mut self, job_types: &[String]) -> Result<Self, String> {
+        if job_types.is_empty() {
+            return Ok(self);
+        }
+
+        let missing: Vec<&str> = job_types
+            .iter()
+            .map(String::as_str)
+            .filter(|job_type| !self.handlers.contains_key(job_type))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "No handler registered for job type(s): {} (available: {})",
+                missing.join(", "),
+                self.job_types().join(", ")
+            ));
+        }
+
+        self.handlers.retain(|job_type, _| job_types.iter().any(|wanted| wanted == job_type));
+        Ok(self)
+    }
+
+    pub fn job_types(&self) -> Vec<&'static str> {
+        self.handlers.keys().copied().collect()
+    }
+
+    pub fn handles(&self, job_type: &str) -> bool {
+        self.handlers.contains_key(job_type)
+    }
+
+    /// Queues this registry's job types live on
+    pub fn queues(&self) -> Vec<&'static str> {
+        let mut queues: Vec<&'static str> = self.handlers.values().map(|registration| registration.queue).collect();
+        queues.sort_unstable();
+        queues.dedup();
+        queues
+    }
+
+    /// Run the handler for a reserved job, then ack, retry or dead-letter it
+    pub async fn process(&self, queue: &mut dyn JobQueue, reserved: &ReservedJob) {
+        let job_type = reserved.envelope.as_ref().map(|envelope| envelope.job_type.as_str());
+
+        let result = match job_type.and_then(|job_type| self.handlers.get(job_type)) {
+            Some(registration) => registration.handler.run(reserved, queue).await,
+            None => Err(JobFailure::Fatal(match job_type {
+                Some(job_type) => format!("No handler for job type {}", job_type),
+                None => JobError::Unversioned.to_string(),
+            })),
+        };
+
+        match result {
+            Ok(()) => {
+                if let Err(e) = queue.ack(reserved).await {
+                    // Still in flight - it will be reclaimed and redelivered
+                    eprintln!("⚠️  Failed to ack job {}: {}", reserved.id, e);
+                }
+            }
+            Err(JobFailure::Retry(error)) => {
+                eprintln!("❌ Job {} failed: {}", reserved.id, error);
+                if let Err(e) = queue.retry_later(reserved, &error).await {
+                    // Still in flight - reclaimed after the visibility timeout
+                    eprintln!("⚠️  Failed to schedule retry for job {}: {}", reserved.id, e);
+                }
+            }
+            Err(JobFailure::Fatal(error)) => {
+                // Retrying can't fix it - straight to the dead-letter queue
+                eprintln!("❌ Rejected job {}: {}", reserved.id, error);
+                if let Err(e) = queue.dead_letter(reserved, &error).await {
+                    eprintln!("⚠️  Failed to dead-letter job {}: {}", reserved.id, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+
+use super::{JobFailure, JobHandler};
+use crate::services::jobs::WebhookDeliveryJob;
+use crate::services::queue::JobQueue;
+use crate::services::webhooks::WebhookDispatcher;
+
+/// Delivers one webhook event to one endpoint per attempt
+pub struct WebhookDeliveryHandler {
+    pub webhooks: WebhookDispatcher,
+}
+
+#[async_trait]
+impl JobHandler for WebhookDeliveryHandler {
+    type Job = WebhookDeliveryJob;
+
+    async fn handle(&self, job: WebhookDeliveryJob, _queue: &mut dyn JobQueue) -> Result<(), JobFailure> {
+        let endpoint = match self.webhooks.endpoint(job.endpoint_id).await {
+            Ok(Some(endpoint)) if endpoint.active => endpoint,
+            Ok(_) => {
+                // Deleted or disabled since the job was queued
+                println!("⏭️  Webhook endpoint {} gone or inactive, skipping {}", job.endpoint_id, job.event.id);
+                return Ok(());
+            }
+            Err(e) => return Err(JobFailure::Retry(format!("Failed to load webhook endpoint: {}", e))),
+        };
+
+        let attempt = self
+            .webhooks
+            .next_attempt(endpoint.id, job.event.id)
+            .await
+            .map_err(|e| JobFailure::Retry(format!("Failed to count webhook attempts: {}", e)))?;
+
+        let result = self.webhooks.deliver(&endpoint, &job.event, attempt).await;
+        if result.succeeded() {
+            println!("📨 Webhook {} delivered to {} (attempt {})", job.event.event_type, endpoint.url, attempt);
+            return Ok(());
+        }
+
+        let error = result
+            .error
+            .unwrap_or_else(|| format!("HTTP {}", result.status_code.unwrap_or_default()));
+        Err(JobFailure::Retry(format!("Webhook {} to {} failed: {}", job.event.event_type, endpoint.url, error)))
+    }
+}